@@ -32,7 +32,7 @@ fn find<'a>(graph: &'a PathRouter<String>, path: &str) -> Option<&'a String> {
     graph.match_path(path, &mut BTreeMap::default())
 }
 
-fn find2<'a>(routes: &Vec<Route<'static>>, path: &str) -> Option<usize> {
+fn find2(routes: &Vec<Route<'static>>, path: &str) -> Option<usize> {
     for (kv, route) in routes.iter().enumerate() {
         if route.match_path(path, &mut BTreeMap::default()) {
             return Some(kv);
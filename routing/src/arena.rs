@@ -53,8 +53,13 @@ impl<T> Arena<T> {
     pub fn iter_mut(&mut self) -> core::slice::IterMut<'_, T> {
         self.inner.iter_mut()
     }
+}
+
+impl<T> IntoIterator for Arena<T> {
+    type Item = T;
+    type IntoIter = alloc::vec::IntoIter<T>;
 
-    pub fn into_iter(self) -> alloc::vec::IntoIter<T> {
+    fn into_iter(self) -> Self::IntoIter {
         self.inner.into_iter()
     }
 }
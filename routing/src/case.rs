@@ -0,0 +1,132 @@
+use alloc::{borrow::Cow, string::String};
+
+/// How constants in a template are compared with the path.
+/// Captured parameter values are never folded
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum CaseSensitivity {
+    #[default]
+    Sensitive,
+    /// `A-Z` match `a-z`, everything else is compared as is
+    AsciiInsensitive,
+    /// Unicode simple case folding, one char always folds to one char
+    Insensitive,
+}
+
+impl CaseSensitivity {
+    pub fn is_sensitive(&self) -> bool {
+        *self == CaseSensitivity::Sensitive
+    }
+
+    /// Fold the text, two texts are equal under this sensitivity when their foldings are
+    pub fn fold<'a>(&self, input: &'a str) -> Cow<'a, str> {
+        if input.chars().all(|m| self.fold_char(m) == m) {
+            return Cow::Borrowed(input);
+        }
+        Cow::Owned(input.chars().map(|m| self.fold_char(m)).collect::<String>())
+    }
+
+    pub fn eq(&self, a: &str, b: &str) -> bool {
+        match self {
+            CaseSensitivity::Sensitive => a == b,
+            CaseSensitivity::AsciiInsensitive => a.eq_ignore_ascii_case(b),
+            CaseSensitivity::Insensitive => a.chars().map(fold_char).eq(b.chars().map(fold_char)),
+        }
+    }
+
+    /// Like [`str::strip_prefix`], comparing the prefix under this sensitivity
+    pub(crate) fn strip_prefix<'v>(&self, value: &'v str, prefix: &str) -> Option<&'v str> {
+        if self.is_sensitive() {
+            return value.strip_prefix(prefix);
+        }
+
+        let mut chars = value.char_indices();
+        for expected in prefix.chars() {
+            let (_, ch) = chars.next()?;
+            if self.fold_char(ch) != self.fold_char(expected) {
+                return None;
+            }
+        }
+
+        Some(chars.as_str())
+    }
+
+    /// Byte offsets in `value` where `needle` starts
+    pub(crate) fn match_indices<'v>(
+        &'v self,
+        value: &'v str,
+        needle: &'v str,
+    ) -> impl Iterator<Item = usize> + 'v {
+        value
+            .char_indices()
+            .map(|(idx, _)| idx)
+            .filter(move |idx| self.strip_prefix(&value[*idx..], needle).is_some())
+    }
+
+    fn fold_char(&self, ch: char) -> char {
+        match self {
+            CaseSensitivity::Sensitive => ch,
+            CaseSensitivity::AsciiInsensitive => ch.to_ascii_lowercase(),
+            CaseSensitivity::Insensitive => fold_char(ch),
+        }
+    }
+}
+
+fn fold_char(ch: char) -> char {
+    if ch.is_ascii() {
+        return ch.to_ascii_lowercase();
+    }
+
+    // Chars whose simple folding differs from their lowercase form
+    match ch {
+        'ς' => 'σ',
+        'ϐ' => 'β',
+        'ϑ' => 'θ',
+        'ϕ' => 'φ',
+        'ϖ' => 'π',
+        'ϰ' => 'κ',
+        'ϱ' => 'ρ',
+        'ϵ' => 'ε',
+        'ſ' => 's',
+        'ẛ' => 'ṡ',
+        '\u{1FBE}' => 'ι',
+        '\u{345}' => 'ι',
+        _ => {
+            let mut lower = ch.to_lowercase();
+            match (lower.next(), lower.next()) {
+                (Some(lower), None) => lower,
+                // Full foldings expanding to several chars are left out
+                _ => ch,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fold() {
+        let ascii = CaseSensitivity::AsciiInsensitive;
+        assert_eq!(ascii.fold("About"), "about");
+        assert_eq!(ascii.fold("ÉTÉ"), "ÉtÉ");
+        assert!(ascii.eq("ABOUT", "about"));
+        assert!(!ascii.eq("ÉTÉ", "été"));
+
+        let unicode = CaseSensitivity::Insensitive;
+        assert_eq!(unicode.fold("ÉTÉ"), "été");
+        assert_eq!(unicode.fold("ΣΟΦΟΣ"), unicode.fold("σοφος"));
+        assert!(unicode.eq("Straße", "STRAßE"));
+        assert!(unicode.eq("\u{212A}elvin", "kelvin"));
+
+        assert!(!CaseSensitivity::Sensitive.eq("About", "about"));
+        assert_eq!(unicode.strip_prefix("\u{212A}.json", "k"), Some(".json"));
+        assert_eq!(
+            unicode
+                .match_indices("aXbxc", "x")
+                .collect::<alloc::vec::Vec<_>>(),
+            [1, 3]
+        );
+    }
+}
@@ -0,0 +1,375 @@
+use alloc::{borrow::Cow, fmt, string::ToString};
+
+/// Integer type a parameter must parse as, eg. `:id<u64>`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IntKind {
+    U8,
+    U16,
+    U32,
+    U64,
+    Usize,
+    I8,
+    I16,
+    I32,
+    I64,
+    Isize,
+    /// Alias for `i64`
+    Int,
+    /// Alias for `u64`
+    Uint,
+}
+
+impl IntKind {
+    const ALL: [IntKind; 12] = [
+        IntKind::U8,
+        IntKind::U16,
+        IntKind::U32,
+        IntKind::U64,
+        IntKind::Usize,
+        IntKind::I8,
+        IntKind::I16,
+        IntKind::I32,
+        IntKind::I64,
+        IntKind::Isize,
+        IntKind::Int,
+        IntKind::Uint,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntKind::U8 => "u8",
+            IntKind::U16 => "u16",
+            IntKind::U32 => "u32",
+            IntKind::U64 => "u64",
+            IntKind::Usize => "usize",
+            IntKind::I8 => "i8",
+            IntKind::I16 => "i16",
+            IntKind::I32 => "i32",
+            IntKind::I64 => "i64",
+            IntKind::Isize => "isize",
+            IntKind::Int => "int",
+            IntKind::Uint => "uint",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<IntKind> {
+        IntKind::ALL.iter().copied().find(|m| m.name() == name)
+    }
+
+    fn bounds(&self) -> (i128, i128) {
+        match self {
+            IntKind::U8 => (0, u8::MAX as i128),
+            IntKind::U16 => (0, u16::MAX as i128),
+            IntKind::U32 => (0, u32::MAX as i128),
+            IntKind::U64 | IntKind::Uint => (0, u64::MAX as i128),
+            IntKind::Usize => (0, usize::MAX as i128),
+            IntKind::I8 => (i8::MIN as i128, i8::MAX as i128),
+            IntKind::I16 => (i16::MIN as i128, i16::MAX as i128),
+            IntKind::I32 => (i32::MIN as i128, i32::MAX as i128),
+            IntKind::I64 | IntKind::Int => (i64::MIN as i128, i64::MAX as i128),
+            IntKind::Isize => (isize::MIN as i128, isize::MAX as i128),
+        }
+    }
+}
+
+/// Inclusive integer range, eg. `1..=9`, `1..` or `..=9`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IntRange {
+    pub start: Option<i128>,
+    pub end: Option<i128>,
+}
+
+impl IntRange {
+    pub fn contains(&self, value: i128) -> bool {
+        self.start.map(|start| start <= value).unwrap_or(true)
+            && self.end.map(|end| value <= end).unwrap_or(true)
+    }
+}
+
+impl fmt::Display for IntRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(start) = self.start {
+            write!(f, "{}", start)?;
+        }
+        f.write_str("..")?;
+        if let Some(end) = self.end {
+            write!(f, "={}", end)?;
+        }
+        Ok(())
+    }
+}
+
+/// Restriction on the values a parameter accepts
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Constraint<'a> {
+    /// `:id<u64>` or `:n<int(1..=9)>`
+    Integer(IntKind, Option<IntRange>),
+    /// `:slug<[a-z0-9-]+>`. The whole value must match the pattern.
+    /// Requires the `regex` feature, without it patterns are rejected when parsed.
+    Pattern(Cow<'a, str>),
+}
+
+impl<'a> Constraint<'a> {
+    pub fn to_owned(self) -> Constraint<'static> {
+        match self {
+            Constraint::Integer(kind, range) => Constraint::Integer(kind, range),
+            Constraint::Pattern(pattern) => Constraint::Pattern(pattern.to_string().into()),
+        }
+    }
+
+    /// Parse the body of a constraint, ie. whatever is between `<` and `>`
+    pub fn parse(input: &'a str) -> Result<Constraint<'a>, ConstraintError> {
+        if input.is_empty() {
+            return Err(ConstraintError::Empty);
+        }
+
+        let (name, range) = match input.find('(') {
+            Some(idx) if input.ends_with(')') => {
+                (&input[..idx], Some(&input[idx + 1..input.len() - 1]))
+            }
+            _ => (input, None),
+        };
+
+        if let Some(kind) = IntKind::from_name(name) {
+            let range = range.map(parse_range).transpose()?;
+            return Ok(Constraint::Integer(kind, range));
+        }
+
+        Constraint::pattern(input)
+    }
+
+    /// Create a pattern constraint, validating the pattern
+    pub fn pattern(pattern: impl Into<Cow<'a, str>>) -> Result<Constraint<'a>, ConstraintError> {
+        let pattern = pattern.into();
+        Regex::new(&pattern)?;
+        Ok(Constraint::Pattern(pattern))
+    }
+
+    /// Check a value against the constraint.
+    /// Patterns are compiled on each call, [`PathRouter`](crate::PathRouter) compiles them once on registration
+    pub fn is_match(&self, value: &str) -> bool {
+        match self {
+            Constraint::Integer(kind, range) => is_int_match(*kind, range.as_ref(), value),
+            Constraint::Pattern(pattern) => Regex::new(pattern)
+                .map(|m| m.is_match(value))
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Constraint<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constraint::Integer(kind, None) => f.write_str(kind.name()),
+            Constraint::Integer(kind, Some(range)) => write!(f, "{}({})", kind.name(), range),
+            Constraint::Pattern(pattern) => f.write_str(pattern),
+        }
+    }
+}
+
+fn is_int_match(kind: IntKind, range: Option<&IntRange>, value: &str) -> bool {
+    let Ok(value) = value.parse::<i128>() else {
+        return false;
+    };
+    let (min, max) = kind.bounds();
+    min <= value && value <= max && range.map(|m| m.contains(value)).unwrap_or(true)
+}
+
+fn parse_range(input: &str) -> Result<IntRange, ConstraintError> {
+    let Some((start, end)) = input.split_once("..") else {
+        return Err(ConstraintError::InvalidRange);
+    };
+
+    let parse_int = |s: &str| {
+        s.trim()
+            .parse::<i128>()
+            .map_err(|_| ConstraintError::InvalidRange)
+    };
+
+    let start = match start.trim() {
+        "" => None,
+        start => Some(parse_int(start)?),
+    };
+
+    let end = if let Some(end) = end.strip_prefix('=') {
+        Some(parse_int(end)?)
+    } else if end.trim().is_empty() {
+        None
+    } else {
+        Some(
+            parse_int(end)?
+                .checked_sub(1)
+                .ok_or(ConstraintError::InvalidRange)?,
+        )
+    };
+
+    if let (Some(start), Some(end)) = (start, end) {
+        if start > end {
+            return Err(ConstraintError::InvalidRange);
+        }
+    }
+
+    Ok(IntRange { start, end })
+}
+
+#[derive(Debug)]
+pub enum ConstraintError {
+    Empty,
+    InvalidRange,
+    InvalidPattern(alloc::string::String),
+}
+
+impl fmt::Display for ConstraintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstraintError::Empty => f.write_str("empty constraint"),
+            ConstraintError::InvalidRange => f.write_str("invalid integer range"),
+            ConstraintError::InvalidPattern(err) => write!(f, "invalid pattern: {}", err),
+        }
+    }
+}
+
+impl core::error::Error for ConstraintError {}
+
+/// A constraint ready for matching, patterns are compiled up front
+#[derive(Debug, Clone)]
+pub(crate) struct CompiledConstraint {
+    constraint: Constraint<'static>,
+    regex: Option<Regex>,
+}
+
+impl CompiledConstraint {
+    pub fn new(constraint: Constraint<'static>) -> CompiledConstraint {
+        let regex = match &constraint {
+            Constraint::Pattern(pattern) => Regex::new(pattern).ok(),
+            Constraint::Integer(..) => None,
+        };
+        CompiledConstraint { constraint, regex }
+    }
+
+    pub fn constraint(&self) -> &Constraint<'static> {
+        &self.constraint
+    }
+
+    pub fn is_match(&self, value: &str) -> bool {
+        match &self.constraint {
+            Constraint::Integer(kind, range) => is_int_match(*kind, range.as_ref(), value),
+            Constraint::Pattern(_) => self
+                .regex
+                .as_ref()
+                .map(|m| m.is_match(value))
+                .unwrap_or(false),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CompiledConstraint {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.constraint.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CompiledConstraint {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let constraint = Constraint::deserialize(deserializer)?;
+        Ok(CompiledConstraint::new(constraint.to_owned()))
+    }
+}
+
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone)]
+struct Regex(regex::Regex);
+
+#[cfg(feature = "regex")]
+impl Regex {
+    fn new(pattern: &str) -> Result<Regex, ConstraintError> {
+        regex::Regex::new(&alloc::format!("^(?:{})$", pattern))
+            .map(Regex)
+            .map_err(|err| ConstraintError::InvalidPattern(err.to_string()))
+    }
+
+    fn is_match(&self, value: &str) -> bool {
+        self.0.is_match(value)
+    }
+}
+
+#[cfg(not(feature = "regex"))]
+#[derive(Debug, Clone)]
+enum Regex {}
+
+#[cfg(not(feature = "regex"))]
+impl Regex {
+    fn new(_pattern: &str) -> Result<Regex, ConstraintError> {
+        Err(ConstraintError::InvalidPattern(
+            "pattern constraints require the `regex` feature".to_string(),
+        ))
+    }
+
+    fn is_match(&self, _value: &str) -> bool {
+        match *self {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_constraint() {
+        assert_eq!(
+            Constraint::parse("u64").unwrap(),
+            Constraint::Integer(IntKind::U64, None)
+        );
+        assert_eq!(
+            Constraint::parse("int(1..=9)").unwrap(),
+            Constraint::Integer(
+                IntKind::Int,
+                Some(IntRange {
+                    start: Some(1),
+                    end: Some(9)
+                })
+            )
+        );
+        assert_eq!(
+            Constraint::parse("int(1..10)").unwrap(),
+            Constraint::parse("int(1..=9)").unwrap()
+        );
+        assert!(Constraint::parse("int(9..1)").is_err());
+        assert!(matches!(
+            Constraint::parse("int(..-170141183460469231731687303715884105728)"),
+            Err(ConstraintError::InvalidRange)
+        ));
+        assert!(Constraint::parse("").is_err());
+    }
+
+    #[test]
+    fn test_integer_constraint() {
+        let c = Constraint::parse("u8").unwrap();
+        assert!(c.is_match("0"));
+        assert!(c.is_match("255"));
+        assert!(!c.is_match("256"));
+        assert!(!c.is_match("-1"));
+        assert!(!c.is_match("new"));
+
+        let c = Constraint::parse("int(1..=9)").unwrap();
+        assert!(c.is_match("1"));
+        assert!(c.is_match("9"));
+        assert!(!c.is_match("0"));
+        assert!(!c.is_match("10"));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_pattern_constraint() {
+        let c = Constraint::parse("[a-z0-9-]+").unwrap();
+        assert!(c.is_match("hello-world-2"));
+        assert!(!c.is_match("Hello"));
+        assert!(!c.is_match(""));
+        assert!(Constraint::parse("[a-z").is_err());
+    }
+}
@@ -0,0 +1,555 @@
+use alloc::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt,
+    string::{String, ToString},
+    vec::{self, Vec},
+};
+use serde::de::{
+    self, value::BorrowedStrDeserializer, DeserializeSeed, Deserializer, MapAccess, SeqAccess,
+    Unexpected, Visitor,
+};
+
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+use crate::{Match, OwnedMatch, PathParams};
+
+/// Deserialize captured parameters into `T`.
+///
+/// Structs and maps are filled by name, tuples and sequences by position, and a lone
+/// parameter can be read straight into a primitive, a newtype or an enum of unit variants.
+/// Values are parsed from their text, an empty value is `None` for an `Option`
+pub fn from_params<'de, T, P>(params: &'de P) -> Result<T, ParamsError>
+where
+    T: de::Deserialize<'de>,
+    P: ParamSource + ?Sized,
+{
+    T::deserialize(ParamsDeserializer {
+        entries: params.entries(),
+    })
+}
+
+/// Captured parameters which can be read back, see [`from_params`].
+/// Maps have no path order so tuples read them in key order
+pub trait ParamSource {
+    fn entries(&self) -> Vec<(&str, &str)>;
+}
+
+impl ParamSource for BTreeMap<String, String> {
+    fn entries(&self) -> Vec<(&str, &str)> {
+        self.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl ParamSource for std::collections::HashMap<String, String> {
+    fn entries(&self) -> Vec<(&str, &str)> {
+        self.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl ParamSource for std::collections::HashMap<Arc<str>, Arc<str>> {
+    fn entries(&self) -> Vec<(&str, &str)> {
+        self.iter().map(|(k, v)| (&**k, &**v)).collect()
+    }
+}
+
+impl<'s, 'p> ParamSource for [(&'s str, Cow<'p, str>)] {
+    fn entries(&self) -> Vec<(&str, &str)> {
+        self.iter().map(|(k, v)| (*k, v.as_ref())).collect()
+    }
+}
+
+impl<'s, 'p> ParamSource for Vec<(&'s str, Cow<'p, str>)> {
+    fn entries(&self) -> Vec<(&str, &str)> {
+        self.as_slice().entries()
+    }
+}
+
+impl<'r, 'p, const N: usize> ParamSource for PathParams<'r, 'p, N> {
+    fn entries(&self) -> Vec<(&str, &str)> {
+        self.iter().collect()
+    }
+}
+
+impl<'r, 'p, H> ParamSource for Match<'r, 'p, H> {
+    fn entries(&self) -> Vec<(&str, &str)> {
+        self.params.entries()
+    }
+}
+
+impl<H> ParamSource for OwnedMatch<H> {
+    fn entries(&self) -> Vec<(&str, &str)> {
+        self.params
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect()
+    }
+}
+
+/// Why parameters couldn't be deserialized, naming the parameter when there is one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamsError {
+    param: Option<String>,
+    message: String,
+}
+
+impl ParamsError {
+    /// The offending parameter
+    pub fn param(&self) -> Option<&str> {
+        self.param.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn at(mut self, name: &str) -> ParamsError {
+        if self.param.is_none() {
+            self.param = Some(name.into());
+        }
+        self
+    }
+}
+
+impl fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.param {
+            Some(param) => write!(f, "parameter `{}`: {}", param, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl core::error::Error for ParamsError {}
+
+impl de::Error for ParamsError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ParamsError {
+            param: None,
+            message: msg.to_string(),
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        ParamsError {
+            param: Some(field.into()),
+            message: "missing".into(),
+        }
+    }
+}
+
+struct ParamsDeserializer<'de> {
+    entries: Vec<(&'de str, &'de str)>,
+}
+
+impl<'de> ParamsDeserializer<'de> {
+    fn single(self) -> Result<ValueDeserializer<'de>, ParamsError> {
+        match self.entries.as_slice() {
+            [(name, value)] => Ok(ValueDeserializer { name, value }),
+            entries => Err(de::Error::custom(format_args!(
+                "expected a single parameter, found {}",
+                entries.len()
+            ))),
+        }
+    }
+
+    fn seq<V: Visitor<'de>>(self, len: Option<usize>, visitor: V) -> Result<V::Value, ParamsError> {
+        match len {
+            Some(len) if len != self.entries.len() => {
+                Err(de::Error::invalid_length(self.entries.len(), &visitor))
+            }
+            _ => visitor.visit_seq(ParamsSeq {
+                entries: self.entries.into_iter(),
+            }),
+        }
+    }
+}
+
+macro_rules! forward_single {
+    ($($method: ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ParamsDeserializer<'de> {
+    type Error = ParamsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    forward_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_identifier
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.entries.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.seq(None, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.seq(Some(len), visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.seq(Some(len), visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(ParamsMap {
+            entries: self.entries.into_iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+}
+
+struct ParamsMap<'de> {
+    entries: vec::IntoIter<(&'de str, &'de str)>,
+    value: Option<(&'de str, &'de str)>,
+}
+
+impl<'de> MapAccess<'de> for ParamsMap<'de> {
+    type Error = ParamsError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        let Some((name, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some((name, value));
+        seed.deserialize(BorrowedStrDeserializer::new(name))
+            .map(Some)
+            .map_err(|err: ParamsError| err.at(name))
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<S::Value, Self::Error> {
+        let (name, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before its name"))?;
+        seed.deserialize(ValueDeserializer { name, value })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct ParamsSeq<'de> {
+    entries: vec::IntoIter<(&'de str, &'de str)>,
+}
+
+impl<'de> SeqAccess<'de> for ParamsSeq<'de> {
+    type Error = ParamsError;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, Self::Error> {
+        match self.entries.next() {
+            Some((name, value)) => seed
+                .deserialize(ValueDeserializer { name, value })
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// A single captured value, parsed from its text
+struct ValueDeserializer<'de> {
+    name: &'de str,
+    value: &'de str,
+}
+
+impl<'de> ValueDeserializer<'de> {
+    fn invalid<V: Visitor<'de>>(self, visitor: &V) -> ParamsError {
+        let err: ParamsError = de::Error::invalid_type(Unexpected::Str(self.value), visitor);
+        err.at(self.name)
+    }
+}
+
+macro_rules! parse_value {
+    ($($method: ident => $visit: ident($ty: ty)),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.value.parse::<$ty>() {
+                    Ok(value) => visitor.$visit(value).map_err(|err: ParamsError| err.at(self.name)),
+                    Err(err) => Err(<ParamsError as de::Error>::custom(err).at(self.name)),
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! reject_value {
+    ($($method: ident$(($($arg: ident: $ty: ty),*))?)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($($arg: $ty,)*)? visitor: V) -> Result<V::Value, Self::Error> {
+                Err(self.invalid(&visitor))
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = ParamsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    parse_value! {
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+        deserialize_char => visit_char(char)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let name = self.name;
+        visitor
+            .visit_borrowed_str(self.value)
+            .map_err(|err: ParamsError| err.at(name))
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let name = self.name;
+        visitor
+            .visit_borrowed_bytes(self.value.as_bytes())
+            .map_err(|err: ParamsError| err.at(name))
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.value.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    reject_value! {
+        deserialize_seq
+        deserialize_tuple(_len: usize)
+        deserialize_tuple_struct(_name: &'static str, _len: usize)
+        deserialize_map
+        deserialize_struct(_name: &'static str, _fields: &'static [&'static str])
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let name = self.name;
+        visitor
+            .visit_enum(BorrowedStrDeserializer::new(self.value))
+            .map_err(|err: ParamsError| err.at(name))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Format {
+        Json,
+        Html,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct UserId(u64);
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Show<'a> {
+        id: UserId,
+        name: &'a str,
+        format: Format,
+        page: Option<u32>,
+    }
+
+    #[test]
+    fn test_from_params() {
+        let mut params = BTreeMap::new();
+        params.insert("id".to_string(), "10".to_string());
+        params.insert("name".to_string(), "rasmus".to_string());
+        params.insert("format".to_string(), "json".to_string());
+
+        let show: Show = from_params(&params).unwrap();
+        assert_eq!(
+            show,
+            Show {
+                id: UserId(10),
+                name: "rasmus",
+                format: Format::Json,
+                page: None
+            }
+        );
+
+        let captures: Vec<(&str, Cow<str>)> =
+            vec![("owner", "kildevaeld".into()), ("id", "7".into())];
+        let (owner, id): (String, u32) = from_params(&captures).unwrap();
+        assert_eq!((owner.as_str(), id), ("kildevaeld", 7));
+
+        let mut single = PathParams::<'_, '_, 1>::new();
+        single.push("format", "html".into());
+        assert_eq!(from_params::<Format, _>(&single), Ok(Format::Html));
+        assert_eq!(
+            from_params::<Option<Format>, _>(&single),
+            Ok(Some(Format::Html))
+        );
+    }
+
+    #[test]
+    fn test_from_params_errors() {
+        let captures: Vec<(&str, Cow<str>)> = vec![
+            ("id", "ten".into()),
+            ("name", "rasmus".into()),
+            ("format", "json".into()),
+        ];
+        let err = from_params::<Show, _>(&captures).unwrap_err();
+        assert_eq!(err.param(), Some("id"));
+        assert_eq!(
+            err.to_string(),
+            "parameter `id`: invalid digit found in string"
+        );
+
+        let captures: Vec<(&str, Cow<str>)> = vec![("id", "10".into()), ("format", "xml".into())];
+        let err = from_params::<Show, _>(&captures).unwrap_err();
+        assert_eq!(err.param(), Some("format"));
+
+        let captures: Vec<(&str, Cow<str>)> = vec![("id", "10".into()), ("format", "json".into())];
+        let err = from_params::<Show, _>(&captures).unwrap_err();
+        assert_eq!(err.to_string(), "parameter `name`: missing");
+
+        assert!(from_params::<(u32, u32), _>(&captures).is_err());
+        assert!(from_params::<u32, _>(&captures).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use alloc::{fmt, string::String, string::ToString};
+
+use crate::{
+    percent::{encode_brace_constant, encode_constant},
+    Constraint, Segment, Segments,
+};
+
+/// Template syntax, used with [`parse_with`](crate::parse_with) and [`Segments::display_as`]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// `/users/:id<u64>/*rest`, the syntax of [`parse`](crate::parse)
+    #[default]
+    Native,
+    /// `/users/{id}/{*rest}` as used by axum and matchit
+    Brace,
+    /// `/users/:id(\d+)/:rest*` as used by express and path-to-regexp
+    Express,
+    /// `/users/{id}` as used by OpenAPI paths
+    OpenApi,
+}
+
+impl Dialect {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dialect::Native => "native",
+            Dialect::Brace => "brace",
+            Dialect::Express => "express",
+            Dialect::OpenApi => "openapi",
+        }
+    }
+
+    /// Whether the segment can be written in this dialect
+    pub fn supports(&self, segment: &Segment<'_>) -> bool {
+        match (self, segment) {
+            (Dialect::Native, _) => true,
+            (_, Segment::Constant(_)) => true,
+            (_, Segment::Parameter(_, None)) => true,
+            (Dialect::Express, Segment::Parameter(_, Some(Constraint::Pattern(_)))) => true,
+            (Dialect::Brace | Dialect::Express, Segment::Star(_)) => true,
+            (Dialect::Express, Segment::Optional(segment)) => {
+                matches!(**segment, Segment::Parameter(..) | Segment::Star(_))
+                    && self.supports(segment)
+            }
+            (_, Segment::Composite(parts)) => parts.iter().all(|m| {
+                matches!(m, Segment::Constant(_) | Segment::Parameter(..)) && self.supports(m)
+            }),
+            _ => false,
+        }
+    }
+
+    fn write_segment(&self, f: &mut fmt::Formatter<'_>, segment: &Segment<'_>) -> fmt::Result {
+        match (self, segment) {
+            (Dialect::Native, segment) => write!(f, "{}", segment),
+            (Dialect::Express, Segment::Constant(c)) => f.write_str(&encode_constant(c)),
+            (Dialect::Brace, Segment::Constant(c)) => f.write_str(&encode_brace_constant(c, true)),
+            (Dialect::OpenApi, Segment::Constant(c)) => {
+                f.write_str(&encode_brace_constant(c, false))
+            }
+            (Dialect::Express, Segment::Parameter(p, None)) => write!(f, ":{}", p),
+            (Dialect::Express, Segment::Parameter(p, Some(c))) => write!(f, ":{}({})", p, c),
+            (_, Segment::Parameter(p, _)) => write!(f, "{{{}}}", p),
+            (Dialect::Express, Segment::Star(s)) => write!(f, ":{}+", s),
+            (_, Segment::Star(s)) => write!(f, "{{*{}}}", s),
+            (_, Segment::Composite(parts)) => {
+                for part in parts {
+                    self.write_segment(f, part)?;
+                }
+                Ok(())
+            }
+            // Not supported by the other dialects
+            (_, Segment::Alternation(..)) => write!(f, "{}", segment),
+            (_, Segment::Optional(segment)) => match &**segment {
+                Segment::Star(s) => write!(f, ":{}*", s),
+                segment => {
+                    self.write_segment(f, segment)?;
+                    f.write_str("?")
+                }
+            },
+        }
+    }
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Template rendered in a dialect, see [`Segments::display_as`]
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayAs<'s, 'a> {
+    pub(crate) segments: &'s Segments<'a>,
+    pub(crate) dialect: Dialect,
+}
+
+impl<'s, 'a> fmt::Display for DisplayAs<'s, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in self.segments.iter() {
+            f.write_str("/")?;
+            self.dialect.write_segment(f, segment)?;
+        }
+        Ok(())
+    }
+}
+
+/// A segment without an equivalent in the requested dialect
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedSegment {
+    pub dialect: Dialect,
+    /// The segment in native syntax
+    pub segment: String,
+}
+
+impl UnsupportedSegment {
+    pub(crate) fn new(dialect: Dialect, segment: &Segment<'_>) -> UnsupportedSegment {
+        UnsupportedSegment {
+            dialect,
+            segment: segment.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for UnsupportedSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` can't be written in the {} dialect",
+            self.segment, self.dialect
+        )
+    }
+}
+
+impl core::error::Error for UnsupportedSegment {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse, parse_with};
+
+    #[test]
+    fn test_display_as() {
+        let segments = parse(r"/users/:id/:name.:ext/ns\:core/*rest").expect("parse");
+        assert_eq!(
+            segments.display_as(Dialect::Brace).unwrap().to_string(),
+            "/users/{id}/{name}.{ext}/ns:core/{*rest}"
+        );
+        assert_eq!(
+            segments.display_as(Dialect::Express).unwrap().to_string(),
+            r"/users/:id/:name.:ext/ns\:core/:rest+"
+        );
+        assert_eq!(
+            segments.display_as(Dialect::Native).unwrap().to_string(),
+            segments.to_string()
+        );
+        assert!(segments.display_as(Dialect::OpenApi).is_err());
+
+        for dialect in [Dialect::Brace, Dialect::Express].iter().copied() {
+            let template = segments.display_as(dialect).unwrap().to_string();
+            assert_eq!(parse_with(dialect, &template).expect("reparse"), segments);
+        }
+
+        let segments = parse("/a%7Bb/:lang?/*rest?").expect("parse");
+        assert_eq!(
+            segments.display_as(Dialect::Express).unwrap().to_string(),
+            "/a%7Bb/:lang?/:rest*"
+        );
+        let err = segments.display_as(Dialect::Brace).unwrap_err();
+        assert_eq!(err.segment, ":lang?");
+
+        let segments = parse("/a%7Bb/:id").expect("parse");
+        assert_eq!(
+            segments.display_as(Dialect::Brace).unwrap().to_string(),
+            "/a{{b/{id}"
+        );
+        assert_eq!(
+            segments.display_as(Dialect::OpenApi).unwrap().to_string(),
+            "/a%7Bb/{id}"
+        );
+        assert!(parse("/:id<u64>")
+            .unwrap()
+            .display_as(Dialect::Express)
+            .is_err());
+    }
+}
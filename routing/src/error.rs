@@ -0,0 +1,159 @@
+use alloc::{fmt, string::String};
+use core::ops::Range;
+
+use crate::ConstraintError;
+
+/// What went wrong while parsing a route template
+#[derive(Debug)]
+pub enum SyntaxErrorKind {
+    /// A character that can't appear at this position
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    /// `:` or `*` without a name, eg. `/users/:`
+    EmptyParameterName,
+    /// The same name is captured twice, eg. `/:id/:id`
+    DuplicateParameterName(String),
+    /// Two parameters without a literal between them, eg. `/:a:b`
+    AdjacentParameters,
+    /// A `%` not followed by two hex digits, or a `\\` before a char which can't be escaped
+    InvalidEscape,
+    /// A `<` without a matching `>`
+    UnterminatedConstraint,
+    InvalidConstraint(ConstraintError),
+}
+
+impl fmt::Display for SyntaxErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyntaxErrorKind::UnexpectedChar(ch) => write!(f, "unexpected character `{}`", ch),
+            SyntaxErrorKind::UnexpectedEnd => f.write_str("unexpected end of template"),
+            SyntaxErrorKind::EmptyParameterName => f.write_str("empty parameter name"),
+            SyntaxErrorKind::DuplicateParameterName(name) => {
+                write!(f, "duplicate parameter name `{}`", name)
+            }
+            SyntaxErrorKind::AdjacentParameters => {
+                f.write_str("parameters must be separated by a literal")
+            }
+            SyntaxErrorKind::InvalidEscape => f.write_str("invalid percent escape"),
+            SyntaxErrorKind::UnterminatedConstraint => f.write_str("unterminated constraint"),
+            SyntaxErrorKind::InvalidConstraint(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Error returned when a route template can't be parsed.
+/// Keeps a copy of the template so it can point at the offending part
+#[derive(Debug)]
+pub struct RouteSyntaxError {
+    kind: SyntaxErrorKind,
+    span: Range<usize>,
+    expected: &'static [&'static str],
+    template: String,
+}
+
+impl RouteSyntaxError {
+    pub(crate) fn new(
+        kind: SyntaxErrorKind,
+        span: Range<usize>,
+        expected: &'static [&'static str],
+        template: &str,
+    ) -> RouteSyntaxError {
+        RouteSyntaxError {
+            kind,
+            span,
+            expected,
+            template: template.into(),
+        }
+    }
+
+    pub fn kind(&self) -> &SyntaxErrorKind {
+        &self.kind
+    }
+
+    pub fn into_kind(self) -> SyntaxErrorKind {
+        self.kind
+    }
+
+    /// Byte range in the template
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Byte offset of the error in the template
+    pub fn offset(&self) -> usize {
+        self.span.start
+    }
+
+    /// What the parser would have accepted instead, if anything
+    pub fn expected(&self) -> &'static [&'static str] {
+        self.expected
+    }
+
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+}
+
+impl fmt::Display for RouteSyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+
+        for (idx, expected) in self.expected.iter().enumerate() {
+            let sep = match idx {
+                0 => ", expected ",
+                idx if idx + 1 == self.expected.len() => " or ",
+                _ => ", ",
+            };
+            write!(f, "{}{}", sep, expected)?;
+        }
+
+        write!(f, " at offset {}", self.span.start)?;
+
+        let pad = self.template[..self.span.start].chars().count();
+        let width = self.template[self.span.clone()].chars().count().max(1);
+
+        write!(f, "\n  {}\n  ", self.template)?;
+        for _ in 0..pad {
+            f.write_str(" ")?;
+        }
+        for _ in 0..width {
+            f.write_str("^")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl core::error::Error for RouteSyntaxError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_display() {
+        let err = RouteSyntaxError::new(
+            SyntaxErrorKind::UnexpectedChar(' '),
+            6..7,
+            &["`/`", "end of template"],
+            "/hello world",
+        );
+        assert_eq!(
+            err.to_string(),
+            "unexpected character ` `, expected `/` or end of template at offset 6\n  /hello world\n        ^"
+        );
+
+        let err = RouteSyntaxError::new(
+            SyntaxErrorKind::DuplicateParameterName("id".into()),
+            9..11,
+            &[],
+            "/:id/é/:id",
+        );
+        assert_eq!(
+            err.to_string(),
+            "duplicate parameter name `id` at offset 9\n  /:id/é/:id\n          ^^"
+        );
+    }
+}
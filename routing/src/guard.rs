@@ -0,0 +1,191 @@
+use alloc::borrow::Cow;
+use http::{request::Parts, HeaderName, HeaderValue};
+
+use crate::percent;
+
+/// A condition on the request an [`Entry`](crate::router::Entry) has to pass
+/// besides the method, see [`Router::route_with_guard`](crate::router::Router::route_with_guard)
+pub trait Guard: Send + Sync {
+    fn check(&self, parts: &Parts) -> bool;
+}
+
+impl<F> Guard for F
+where
+    F: Fn(&Parts) -> bool + Send + Sync,
+{
+    fn check(&self, parts: &Parts) -> bool {
+        (self)(parts)
+    }
+}
+
+/// Passes when the header is present with exactly the given value
+pub fn header(name: HeaderName, value: HeaderValue) -> impl Guard {
+    move |parts: &Parts| parts.headers.get_all(&name).iter().any(|m| *m == value)
+}
+
+/// Passes when the query has the parameter with the given value, both
+/// form-urlencoded
+pub fn query(name: &str, value: &str) -> impl Guard {
+    let name = alloc::string::String::from(name);
+    let value = alloc::string::String::from(value);
+    move |parts: &Parts| {
+        parts
+            .uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+            .any(|(k, v)| decode_form(k) == name && decode_form(v) == value)
+    }
+}
+
+// A `+` stands for a space, escapes are all decoded including `%2F`
+fn decode_form(input: &str) -> Cow<'_, str> {
+    if input.contains('+') {
+        Cow::Owned(percent::decode(&input.replace('+', " "), false).into_owned())
+    } else {
+        percent::decode(input, false)
+    }
+}
+
+/// Passes when the typed header is present and satisfies the predicate
+#[cfg(feature = "headers")]
+pub fn typed<T, F>(predicate: F) -> impl Guard
+where
+    T: headers::Header,
+    F: Fn(&T) -> bool + Send + Sync,
+{
+    move |parts: &Parts| {
+        use headers::HeaderMapExt;
+        parts
+            .headers
+            .typed_get::<T>()
+            .map(|header| predicate(&header))
+            .unwrap_or(false)
+    }
+}
+
+/// Passes when the body has the media type, parameters like the charset are ignored
+#[cfg(feature = "headers")]
+pub fn content_type(mime: mime::Mime) -> impl Guard {
+    typed(move |content_type: &headers::ContentType| {
+        mime::Mime::from(content_type.clone()).essence_str() == mime.essence_str()
+    })
+}
+
+/// Passes when the `Accept` header allows the media type. The most specific range
+/// matching it decides, so `*/*, image/png;q=0` refuses PNG. A request without
+/// an `Accept` header accepts anything
+#[cfg(feature = "headers")]
+pub fn accept(mime: mime::Mime) -> impl Guard {
+    move |parts: &Parts| {
+        let mut accepts = parts
+            .headers
+            .get_all(http::header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|range| range.trim().parse::<mime::Mime>().ok())
+            .peekable();
+
+        if accepts.peek().is_none() {
+            return true;
+        }
+
+        let best = accepts
+            .filter_map(|range| {
+                let specificity = if range.type_() == mime::STAR && range.subtype() == mime::STAR {
+                    0
+                } else if range.type_() != mime.type_() {
+                    return None;
+                } else if range.subtype() == mime::STAR {
+                    1
+                } else if range.subtype() == mime.subtype() {
+                    2
+                } else {
+                    return None;
+                };
+
+                let q = range
+                    .get_param("q")
+                    .and_then(|q| q.as_str().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                Some((specificity, q))
+            })
+            .max_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+
+        best.is_some_and(|(_, q)| q > 0.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use http::Request;
+
+    fn parts(request: Request<()>) -> Parts {
+        request.into_parts().0
+    }
+
+    #[test]
+    fn test_guards() {
+        let request = parts(
+            Request::get("/search?q=a%20b&page=2")
+                .header("x-version", "2")
+                .body(())
+                .unwrap(),
+        );
+
+        let version = header(
+            HeaderName::from_static("x-version"),
+            HeaderValue::from_static("2"),
+        );
+        assert!(version.check(&request));
+        assert!(query("q", "a b").check(&request));
+        assert!(!query("page", "3").check(&request));
+        assert!(!query("missing", "").check(&request));
+
+        let request = parts(
+            Request::get("/search?path=a%2Fb&q=c+d&sum=1%2B1")
+                .body(())
+                .unwrap(),
+        );
+        assert!(query("path", "a/b").check(&request));
+        assert!(query("q", "c d").check(&request));
+        assert!(query("sum", "1+1").check(&request));
+    }
+
+    #[cfg(feature = "headers")]
+    #[test]
+    fn test_typed_guards() {
+        let request = parts(
+            Request::post("/")
+                .header("content-type", "application/json; charset=utf-8")
+                .header("accept", "text/html, application/*;q=0.8, image/png;q=0")
+                .body(())
+                .unwrap(),
+        );
+
+        assert!(content_type(mime::APPLICATION_JSON).check(&request));
+        assert!(!content_type(mime::TEXT_PLAIN).check(&request));
+        assert!(accept(mime::TEXT_HTML).check(&request));
+        assert!(accept(mime::APPLICATION_JSON).check(&request));
+        assert!(!accept(mime::IMAGE_PNG).check(&request));
+        assert!(accept(mime::IMAGE_PNG).check(&parts(Request::get("/").body(()).unwrap())));
+
+        let request = |accept_header: &str| {
+            parts(
+                Request::get("/")
+                    .header("accept", accept_header)
+                    .body(())
+                    .unwrap(),
+            )
+        };
+        assert!(!accept(mime::IMAGE_PNG).check(&request("*/*, image/png;q=0")));
+        assert!(accept(mime::IMAGE_GIF).check(&request("*/*, image/png;q=0")));
+        assert!(!accept(mime::IMAGE_PNG).check(&request("image/png;q=0, image/*")));
+        assert!(accept(mime::IMAGE_PNG).check(&request("*/*;q=0, image/*;q=0.5")));
+        assert!(!accept(mime::TEXT_HTML).check(&request("image/*")));
+    }
+}
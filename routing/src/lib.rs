@@ -6,24 +6,49 @@ extern crate alloc;
 extern crate std;
 
 mod arena;
+mod case;
+mod constraint;
+mod dialect;
+mod error;
 mod matcher;
+mod options;
 mod params;
 mod parser;
+mod path;
 pub mod path_router;
+mod percent;
+mod route_match;
 mod segment;
 mod segments;
 
-pub use udled::Error as ParseError;
-
 pub use self::{
     arena::*,
+    case::CaseSensitivity,
+    constraint::{Constraint, ConstraintError, IntKind, IntRange},
+    dialect::{Dialect, DisplayAs, UnsupportedSegment},
+    error::{RouteSyntaxError, RouteSyntaxError as ParseError, SyntaxErrorKind},
     matcher::*,
-    params::Params,
-    parser::parse,
+    options::{EncodedSlash, MatchOptions, TrailingSlash},
+    params::{ParamIndex, Params, PathParams, TryParams},
+    parser::{parse, parse_with},
+    path::{EmptySegments, Normalization, NormalizeError, PathSegments},
     path_router::{PathRouter, Route},
+    route_match::{Match, OwnedMatch, Redirect, Resolved},
     segment::Segment,
     segments::*,
 };
 
+#[cfg(feature = "router")]
+pub mod guard;
+
 #[cfg(feature = "router")]
 pub mod router;
+
+#[cfg(feature = "router")]
+pub mod version;
+
+#[cfg(feature = "serde")]
+mod de;
+
+#[cfg(feature = "serde")]
+pub use self::de::{from_params, ParamSource, ParamsError};
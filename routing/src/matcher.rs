@@ -1,141 +1,361 @@
 use core::ops::Range;
 
-use crate::{Params, Segment};
+use alloc::{borrow::Cow, collections::BTreeSet, string::ToString, vec::Vec};
 
-pub(crate) fn into_segments<'a>(input: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
-    let mut progress = 0usize;
-    let len = input.len();
+use crate::{
+    params::{CaptureSink, TrySink},
+    path::{has_trailing_slash, PathSegments},
+    percent::{decode, decode_param},
+    CaseSensitivity, MatchOptions, Params, Segment, TrailingSlash, TryParams,
+};
 
-    if input.starts_with("/") {
-        progress += 1;
-    }
+// Splits off the empty constant standing for a trailing slash and checks the path
+// against it. A trailing catch-all takes the slash along with the rest of the path.
+fn trailing_slash<'s, 'a>(
+    segments: &'s [Segment<'a>],
+    path: &str,
+    options: &MatchOptions,
+) -> Option<&'s [Segment<'a>]> {
+    let (segments, template) = match segments.split_last() {
+        Some((Segment::Constant(c), rest)) if c.is_empty() => (rest, true),
+        _ => (segments, false),
+    };
 
-    core::iter::from_fn(move || {
-        if progress == input.len() {
-            return None;
-        }
+    let star = matches!(segments.last(), Some(Segment::Star(_)))
+        || matches!(segments.last(), Some(Segment::Optional(m)) if matches!(**m, Segment::Star(_)));
+    let slash = has_trailing_slash(path) && !star;
 
-        let mut current = progress;
+    if options.trailing_slash == TrailingSlash::Ignore || slash == template {
+        Some(segments)
+    } else {
+        None
+    }
+}
 
-        let mut chars = input[progress..].chars().peekable();
+pub fn match_path<'a, 'c, S: AsRef<[Segment<'a>]>, P: Params>(
+    segments: S,
+    path: &str,
+    params: &'c mut P,
+) -> bool {
+    match_path_with(segments, path, params, &MatchOptions::default())
+}
 
-        loop {
-            let next = match chars.next() {
-                Some(ret) => ret,
-                None => break,
-            };
+/// [`TrailingSlash::RedirectToCanonical`] is as strict as `Strict` here, there is nowhere to put the redirect
+pub fn match_path_with<'a, 'c, S: AsRef<[Segment<'a>]>, P: Params>(
+    segments: S,
+    path: &str,
+    params: &'c mut P,
+    options: &MatchOptions,
+) -> bool {
+    let Ok(path) = options.normalization.apply(path) else {
+        return false;
+    };
+    let Some(segments) = trailing_slash(segments.as_ref(), &path, options) else {
+        return false;
+    };
 
-            current += 1;
+    // Captures are only handed to params once the whole path matched,
+    // so branches abandoned while trying optional segments leave no trace
+    let mut captures = Vec::new();
 
-            if next == '/' {
-                if let Some(_) = chars.next_if(|ch| ch == &'/') {
-                    current += 1;
-                } else {
-                    current -= 1;
-                    break;
-                }
-            }
-        }
+    let iter = PathSegments::new(&path).ranges();
+    if !match_segments(
+        segments,
+        &path,
+        iter,
+        options,
+        &mut captures,
+        &mut Failed::new(),
+    ) {
+        return false;
+    }
 
-        if progress == current {
-            None
-        } else {
-            let rg = progress..current;
-            progress = current;
-            if progress != len {
-                progress += 1;
-            }
-            Some(rg)
-        }
-    })
+    for (name, value) in captures {
+        params.set(name.into(), value);
+    }
+
+    true
 }
 
-pub fn match_path<'a, 'c, S: AsRef<[Segment<'a>]>, P: Params>(
+/// Like [`match_path`], writing into `params` as the path is matched. A value `params`
+/// refuses fails that branch and the next candidate is tried, a failed match leaves
+/// `params` at the checkpoint it started from
+pub fn try_match_path<'a, S: AsRef<[Segment<'a>]>, P: TryParams + ?Sized>(
     segments: S,
-    mut path: &str,
-    params: &'c mut P,
+    path: &str,
+    params: &mut P,
 ) -> bool {
-    if path.len() != 0 && path.as_bytes()[0] == b'/' {
-        path = &path[1..];
-    }
+    try_match_path_with(segments, path, params, &MatchOptions::default())
+}
 
-    let segments = segments.as_ref();
+pub fn try_match_path_with<'a, S: AsRef<[Segment<'a>]>, P: TryParams + ?Sized>(
+    segments: S,
+    path: &str,
+    params: &mut P,
+    options: &MatchOptions,
+) -> bool {
+    let Ok(path) = options.normalization.apply(path) else {
+        return false;
+    };
+    let Some(segments) = trailing_slash(segments.as_ref(), &path, options) else {
+        return false;
+    };
 
-    if path.len() == 0 && segments.len() == 0 {
+    let mut sink = TrySink::new(params);
+    let iter = PathSegments::new(&path).ranges();
+    if match_segments(
+        segments,
+        &path,
+        iter,
+        options,
+        &mut sink,
+        &mut Failed::new(),
+    ) {
         return true;
-    } else if path.len() == 0 {
-        return false;
     }
+    sink.rollback(0);
+    false
+}
 
-    let mut segments = segments.iter();
-    let mut current: Option<&Segment<'_>> = None;
+// The rest of the template, by its length, the component it failed to match from and
+// the sink's state. A star tries every split of the path, without remembering failures
+// several stars would try the same tails over and over
+type Failed = BTreeSet<(usize, usize, usize)>;
 
-    let mut iter = into_segments(path);
+fn match_segments<'s, 'p, C: CaptureSink<'s, 'p>>(
+    segments: &'s [Segment<'_>],
+    path: &'p str,
+    mut iter: impl Iterator<Item = Range<usize>> + Clone,
+    options: &MatchOptions,
+    captures: &mut C,
+    failed: &mut Failed,
+) -> bool {
+    let Some((segment, rest)) = segments.split_first() else {
+        return iter.next().is_none();
+    };
 
-    loop {
-        let range = match iter.next() {
-            None => return current.is_some() && segments.next().is_none(),
-            Some(range) => range,
-        };
+    match_segment(segment, rest, path, iter, options, captures, failed)
+}
 
-        current = segments.next();
+fn match_segment<'s, 'p, C: CaptureSink<'s, 'p>>(
+    segment: &'s Segment<'_>,
+    rest: &'s [Segment<'_>],
+    path: &'p str,
+    mut iter: impl Iterator<Item = Range<usize>> + Clone,
+    options: &MatchOptions,
+    captures: &mut C,
+    failed: &mut Failed,
+) -> bool {
+    if let Segment::Optional(inner) = segment {
+        let mark = captures.mark();
+        if match_segment(inner, rest, path, iter.clone(), options, captures, failed) {
+            return true;
+        }
+        captures.rollback(mark);
 
-        match current {
-            Some(Segment::Constant(name)) => {
-                if *name != &path[range] {
+        if let Segment::Star(name) = &**inner {
+            if !captures.capture(name, "".into()) {
+                return false;
+            }
+        }
+
+        return match_segments(rest, path, iter, options, captures, failed);
+    }
+
+    let Some(range) = iter.next() else {
+        return false;
+    };
+
+    // Empty components, as in `/a//b`, are only ever taken by a star
+    if range.is_empty() && !matches!(segment, Segment::Star(_)) {
+        return false;
+    }
+
+    match segment {
+        Segment::Constant(name) => {
+            options
+                .case_sensitivity
+                .eq(name, &decode(&path[range], false))
+                && match_segments(rest, path, iter, options, captures, failed)
+        }
+        Segment::Parameter(name, constraint) => {
+            let Some(value) = decode_param(&path[range], options) else {
+                return false;
+            };
+            if let Some(constraint) = constraint {
+                if !constraint.is_match(&value) {
                     return false;
                 }
             }
-            Some(Segment::Parameter(n)) => {
-                params.set(n.clone(), (&path[range]).into());
+            captures.capture(name, value)
+                && match_segments(rest, path, iter, options, captures, failed)
+        }
+        Segment::Star(name) => {
+            // Grow the star one component at a time until the rest of the template matches
+            let start = range.start;
+            let mut end = range.end;
+            loop {
+                let value = if iter.clone().next().is_none() {
+                    &path[start..]
+                } else {
+                    &path[start..end]
+                };
+
+                let key = (
+                    rest.len(),
+                    iter.clone().next().map_or(path.len(), |m| m.start),
+                    captures.state(),
+                );
+                let value = if failed.contains(&key) {
+                    None
+                } else {
+                    decode_param(value, options)
+                };
+
+                if let Some(value) = value {
+                    let mark = captures.mark();
+                    if captures.capture(name, value) {
+                        if match_segments(rest, path, iter.clone(), options, captures, failed) {
+                            return true;
+                        }
+                        failed.insert(key);
+                    }
+                    captures.rollback(mark);
+                }
+
+                match iter.next() {
+                    Some(next) => end = next.end,
+                    None => return false,
+                }
             }
-            Some(Segment::Star(n)) => {
-                params.set(n.clone(), (&path[range]).into());
-                return true;
+        }
+        Segment::Composite(parts) => {
+            let Some(value) = decode_param(&path[range], options) else {
+                return false;
+            };
+            let accepts = |idx: usize, value: &str| match &parts[idx] {
+                Segment::Parameter(_, Some(constraint)) => constraint.is_match(value),
+                _ => true,
+            };
+            let mark = captures.mark();
+            if !match_composite_decoded(parts, value, options.case_sensitivity, accepts, captures) {
+                captures.rollback(mark);
+                return false;
             }
-            None => return false,
-        };
+            match_segments(rest, path, iter, options, captures, failed)
+        }
+        Segment::Alternation(name, alternatives) => {
+            let value = decode(&path[range], false);
+            if !alternatives
+                .iter()
+                .any(|m| options.case_sensitivity.eq(m, &value))
+            {
+                return false;
+            }
+            if let Some(name) = name {
+                if !captures.capture(name, value) {
+                    return false;
+                }
+            }
+            match_segments(rest, path, iter, options, captures, failed)
+        }
+        Segment::Optional(_) => unreachable!(),
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+/// Like [`match_composite`], but for a component which might have been decoded into an owned string.
+/// Fails when the sink refuses a capture, leaving the ones before it for the caller to roll back
+pub(crate) fn match_composite_decoded<'s, 'p, C: CaptureSink<'s, 'p>>(
+    parts: &'s [Segment<'_>],
+    value: Cow<'p, str>,
+    case: CaseSensitivity,
+    accepts: impl Fn(usize, &str) -> bool + Copy,
+    captures: &mut C,
+) -> bool {
+    match value {
+        Cow::Borrowed(value) => {
+            let mut found = Vec::new();
+            match_composite(parts, value, case, accepts, &mut found)
+                && found
+                    .into_iter()
+                    .all(|(k, v)| captures.capture(k, Cow::Borrowed(v)))
+        }
+        Cow::Owned(value) => {
+            let mut found = Vec::new();
+            match_composite(parts, &value, case, accepts, &mut found)
+                && found
+                    .into_iter()
+                    .all(|(k, v)| captures.capture(k, Cow::Owned(v.to_string())))
+        }
+    }
+}
 
-    use alloc::{collections::BTreeMap, string::String, string::ToString, vec, vec::Vec};
+/// Split one path component against the literal parts of a composite segment.
+/// Parameters are lazy and never empty, so `:name.:ext` captures `archive` and `tar.gz`
+/// from `archive.tar.gz` unless a constraint on `ext` forces a later split.
+pub(crate) fn match_composite<'s, 'p>(
+    parts: &'s [Segment<'_>],
+    value: &'p str,
+    case: CaseSensitivity,
+    accepts: impl Fn(usize, &str) -> bool + Copy,
+    captures: &mut Vec<(&'s str, &'p str)>,
+) -> bool {
+    match_composite_from(parts, 0, value, case, accepts, captures)
+}
 
-    use crate::parser::parse;
+fn match_composite_from<'s, 'p>(
+    parts: &'s [Segment<'_>],
+    idx: usize,
+    value: &'p str,
+    case: CaseSensitivity,
+    accepts: impl Fn(usize, &str) -> bool + Copy,
+    captures: &mut Vec<(&'s str, &'p str)>,
+) -> bool {
+    let Some(part) = parts.get(idx) else {
+        return value.is_empty();
+    };
 
-    macro_rules! segments {
-        ($url: literal => $($segs: literal),*) => {
-            assert_eq!(
-                into_segments($url).map(|m| $url[m].to_string()).collect::<Vec<_>>(),
-                vec![$($segs.to_string()),*]
-            );
+    match part {
+        Segment::Constant(literal) => match case.strip_prefix(value, literal) {
+            Some(rest) => match_composite_from(parts, idx + 1, rest, case, accepts, captures),
+            None => false,
+        },
+        Segment::Parameter(name, _) => {
+            let Some(Segment::Constant(next)) = parts.get(idx + 1) else {
+                // Last part takes the remainder
+                if value.is_empty() || !accepts(idx, value) {
+                    return false;
+                }
+                captures.push((name, value));
+                return true;
+            };
 
-        };
-        ($url: literal) => {
-            assert_eq!(
-                into_segments($url).map(|m| $url[m].to_string()).collect::<Vec<_>>(),
-                Vec::<String>::default()
-            );
+            for pos in case.match_indices(value, next).filter(|pos| *pos > 0) {
+                let capture = &value[..pos];
+                if !accepts(idx, capture) {
+                    continue;
+                }
+                let mark = captures.len();
+                captures.push((name, capture));
+                if match_composite_from(parts, idx + 1, &value[pos..], case, accepts, captures) {
+                    return true;
+                }
+                captures.truncate(mark);
+            }
 
-        };
+            false
+        }
+        _ => false,
     }
+}
 
-    #[test]
-    fn test_into_segments() {
-        segments!("/");
-        segments!("");
-        segments!("/path" => "path");
-        segments!("path" => "path");
-        segments!("path/" => "path");
-        segments!("/path/" => "path");
-        segments!("/path/subpath" => "path", "subpath");
-        segments!("/path/subpath/" => "path", "subpath");
-        segments!("path/subpath/" => "path", "subpath");
-        segments!("https://test.com/test/path/subpath/" => "https://test.com", "test", "path", "subpath");
-    }
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use alloc::{collections::BTreeMap, string::String, string::ToString, vec, vec::Vec};
+
+    use crate::{parser::parse, EmptySegments, Normalization};
 
     #[test]
     fn test_match_path() {
@@ -165,11 +385,317 @@ mod test {
             "/ost",
             &mut params
         ));
-        assert_eq!(params.get("subpath").map(|m| m), Some(&"ost".into()));
+        assert_eq!(params.get("subpath"), Some(&"ost".into()));
         assert!(!match_path(
             parse("/:subpath").expect("parse"),
             "/ost/boef",
             &mut BTreeMap::default()
         ));
     }
+
+    #[test]
+    fn test_match_optional() {
+        let segments = parse("/:lang?/docs").expect("parse");
+        let mut params = BTreeMap::default();
+        assert!(match_path(&segments, "/docs", &mut params));
+        assert_eq!(params.get("lang"), None);
+        assert!(match_path(&segments, "/en/docs", &mut params));
+        assert_eq!(params.get("lang"), Some(&"en".into()));
+        assert!(!match_path(
+            &segments,
+            "/en/other",
+            &mut BTreeMap::default()
+        ));
+
+        let segments = parse("/files/*rest?").expect("parse");
+        let mut params = BTreeMap::default();
+        assert!(match_path(&segments, "/files", &mut params));
+        assert_eq!(params.get("rest"), Some(&"".into()));
+        assert!(match_path(&segments, "/files/a/b.png", &mut params));
+        assert_eq!(params.get("rest"), Some(&"a/b.png".into()));
+    }
+
+    #[test]
+    fn test_match_composite() {
+        let segments = parse("/files/:name.:ext").expect("parse");
+        let mut params = BTreeMap::default();
+        assert!(match_path(&segments, "/files/archive.tar.gz", &mut params));
+        assert_eq!(params.get("name"), Some(&"archive".into()));
+        assert_eq!(params.get("ext"), Some(&"tar.gz".into()));
+        assert!(!match_path(
+            &segments,
+            "/files/archive",
+            &mut BTreeMap::default()
+        ));
+        assert!(!match_path(
+            &segments,
+            "/files/.gz",
+            &mut BTreeMap::default()
+        ));
+
+        let segments = parse("/v:version/api").expect("parse");
+        let mut params = BTreeMap::default();
+        assert!(match_path(&segments, "/v2/api", &mut params));
+        assert_eq!(params.get("version"), Some(&"2".into()));
+        assert!(!match_path(&segments, "/2/api", &mut BTreeMap::default()));
+
+        let segments = parse("/@:user").expect("parse");
+        let mut params = BTreeMap::default();
+        assert!(match_path(&segments, "/@rust", &mut params));
+        assert_eq!(params.get("user"), Some(&"rust".into()));
+    }
+
+    #[test]
+    fn test_match_inner_star() {
+        let segments = parse("/repos/*path/blob/:rev").expect("parse");
+        let mut params = BTreeMap::default();
+        assert!(match_path(
+            &segments,
+            "/repos/rust-lang/rust/blob/master",
+            &mut params
+        ));
+        assert_eq!(params.get("path"), Some(&"rust-lang/rust".into()));
+        assert_eq!(params.get("rev"), Some(&"master".into()));
+        assert!(!match_path(
+            &segments,
+            "/repos/blob/master",
+            &mut BTreeMap::default()
+        ));
+
+        let segments = parse("/*all").expect("parse");
+        let mut params = BTreeMap::default();
+        assert!(match_path(&segments, "/a/b/c", &mut params));
+        assert_eq!(params.get("all"), Some(&"a/b/c".into()));
+    }
+
+    #[test]
+    fn test_match_encoded() {
+        let segments = parse("/hello%20world/:name").expect("parse");
+        let mut params = BTreeMap::default();
+        assert!(match_path(&segments, "/hello%20world/a%20b", &mut params));
+        assert_eq!(params.get("name"), Some(&"a b".into()));
+
+        let mut params = BTreeMap::default();
+        assert!(match_path_with(
+            &segments,
+            "/hello%20world/a%20b",
+            &mut params,
+            &MatchOptions {
+                decode_params: false,
+                ..Default::default()
+            }
+        ));
+        assert_eq!(params.get("name"), Some(&"a%20b".into()));
+    }
+
+    #[test]
+    fn test_match_escaped() {
+        let segments = parse(r"/ns\:core/:item/glob\*").expect("parse");
+        let mut params = BTreeMap::default();
+        assert!(match_path(&segments, "/ns:core/a/glob*", &mut params));
+        assert_eq!(params.get("item"), Some(&"a".into()));
+        assert!(match_path(&segments, "/ns%3Acore/a/glob%2A", &mut params));
+        assert!(!match_path(&segments, "/ns/a/glob", &mut params));
+    }
+
+    #[test]
+    fn test_match_alternation() {
+        let segments = parse("/:kind(users|people)/(a|b)").expect("parse");
+        let mut params = BTreeMap::default();
+        assert!(match_path(&segments, "/people/b", &mut params));
+        assert_eq!(params.get("kind"), Some(&"people".into()));
+        assert_eq!(params.len(), 1);
+        assert!(!match_path(&segments, "/others/a", &mut params));
+        assert!(!match_path(&segments, "/users/c", &mut params));
+    }
+
+    #[test]
+    fn test_match_case_insensitive() {
+        let segments = parse("/About/:name.JSON").expect("parse");
+        let options = MatchOptions {
+            case_sensitivity: CaseSensitivity::AsciiInsensitive,
+            ..Default::default()
+        };
+        let mut params = BTreeMap::default();
+        assert!(match_path_with(
+            &segments,
+            "/ABOUT/Me.json",
+            &mut params,
+            &options
+        ));
+        assert_eq!(params.get("name"), Some(&"Me".into()));
+        assert!(!match_path(&segments, "/ABOUT/Me.json", &mut params));
+    }
+
+    /// Refuses values spanning several components
+    #[derive(Default)]
+    struct SingleComponent(Vec<(String, String)>);
+
+    impl TryParams for SingleComponent {
+        fn try_set(&mut self, key: Cow<'_, str>, value: Cow<'_, str>) -> bool {
+            !value.contains('/') && self.0.try_set(key, value)
+        }
+
+        fn checkpoint(&self) -> usize {
+            self.0.checkpoint()
+        }
+
+        fn rollback(&mut self, key: &str, checkpoint: usize) {
+            self.0.rollback(key, checkpoint)
+        }
+    }
+
+    /// Refuses values past a number of captures
+    struct Limited {
+        values: Vec<(String, String)>,
+        limit: usize,
+    }
+
+    impl TryParams for Limited {
+        fn try_set(&mut self, key: Cow<'_, str>, value: Cow<'_, str>) -> bool {
+            self.values.len() < self.limit && self.values.try_set(key, value)
+        }
+
+        fn checkpoint(&self) -> usize {
+            self.values.checkpoint()
+        }
+
+        fn rollback(&mut self, key: &str, checkpoint: usize) {
+            self.values.rollback(key, checkpoint)
+        }
+    }
+
+    #[test]
+    fn test_try_match_path() {
+        let segments = parse("/files/:kind/*path/raw").unwrap();
+
+        let mut params = SingleComponent::default();
+        assert!(try_match_path(
+            &segments,
+            "/files/text/readme/raw",
+            &mut params
+        ));
+        assert_eq!(
+            params.0,
+            vec![
+                ("kind".to_string(), "text".to_string()),
+                ("path".to_string(), "readme".to_string())
+            ]
+        );
+
+        let mut params = SingleComponent::default();
+        assert!(!try_match_path(
+            &segments,
+            "/files/text/src/lib.rs/raw",
+            &mut params
+        ));
+        assert!(params.0.is_empty());
+
+        let mut params = Vec::new();
+        assert!(try_match_path(
+            &segments,
+            "/files/text/src/lib.rs/raw",
+            &mut params
+        ));
+        assert_eq!(params[1], ("path".to_string(), "src/lib.rs".to_string()));
+    }
+
+    #[test]
+    fn test_try_match_limited() {
+        // Taking `a` leaves no room for `c`, the split of `b` which failed then
+        // has to be tried again without it
+        let segments = parse("/:a?/*b/x/*c/end").unwrap();
+
+        let mut params = Limited {
+            values: Vec::new(),
+            limit: 2,
+        };
+        assert!(try_match_path(&segments, "/p/q/x/r/end", &mut params));
+        assert_eq!(
+            params.values,
+            vec![
+                ("b".to_string(), "p/q".to_string()),
+                ("c".to_string(), "r".to_string())
+            ]
+        );
+
+        let mut params = Limited {
+            values: Vec::new(),
+            limit: 1,
+        };
+        assert!(!try_match_path(&segments, "/p/q/x/r/end", &mut params));
+        assert!(params.values.is_empty());
+    }
+
+    #[test]
+    fn test_match_trailing_slash() {
+        let plain = parse("/about").unwrap();
+        let slashed = parse("/docs/").unwrap();
+        let star = parse("/files/*path").unwrap();
+        let mut params = BTreeMap::default();
+
+        assert!(match_path(&plain, "/about/", &mut params));
+        assert!(match_path(&slashed, "/docs", &mut params));
+
+        let options = MatchOptions {
+            trailing_slash: TrailingSlash::Strict,
+            ..Default::default()
+        };
+        assert!(match_path_with(&plain, "/about", &mut params, &options));
+        assert!(!match_path_with(&plain, "/about/", &mut params, &options));
+        assert!(match_path_with(&slashed, "/docs/", &mut params, &options));
+        assert!(!match_path_with(&slashed, "/docs", &mut params, &options));
+
+        assert!(match_path_with(&star, "/files/src/", &mut params, &options));
+        assert_eq!(params.get("path"), Some(&"src/".into()));
+    }
+
+    #[test]
+    fn test_match_many_stars() {
+        let segments = parse("/*a/x/*b/x/*c/x/*d/end").unwrap();
+        let path = "/x".repeat(120);
+        let mut params = BTreeMap::<String, String>::new();
+        assert!(!match_path(&segments, &path, &mut params));
+
+        let path = alloc::format!("{path}/end");
+        assert!(match_path(&segments, &path, &mut params));
+        assert_eq!(params.get("a").map(|m| m.as_str()), Some("x"));
+    }
+
+    #[test]
+    fn test_match_normalized() {
+        let segments = parse("/static/:file").unwrap();
+        let mut params = BTreeMap::default();
+
+        assert!(!match_path(&segments, "/static//app.js", &mut params));
+        assert!(!match_path(&segments, "/static/css/../app.js", &mut params));
+
+        let options = MatchOptions {
+            normalization: Normalization {
+                remove_dot_segments: true,
+                empty_segments: EmptySegments::Collapse,
+                reject_traversal: true,
+            },
+            ..Default::default()
+        };
+        assert!(match_path_with(
+            &segments,
+            "/static//app.js",
+            &mut params,
+            &options
+        ));
+        assert!(match_path_with(
+            &segments,
+            "/static/css/../app.js",
+            &mut params,
+            &options
+        ));
+        assert_eq!(params.get("file"), Some(&"app.js".into()));
+        assert!(!match_path_with(
+            &segments,
+            "/static/../../app.js",
+            &mut params,
+            &options
+        ));
+    }
 }
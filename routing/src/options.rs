@@ -0,0 +1,57 @@
+use crate::{CaseSensitivity, Normalization};
+
+/// What to do with `%2F` inside a captured parameter value
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EncodedSlash {
+    /// The parameter doesn't match values containing `%2F`
+    Reject,
+    /// `%2F` is left encoded while everything else is decoded
+    #[default]
+    Keep,
+    /// `%2F` is decoded to `/` like any other escape
+    Decode,
+}
+
+/// How a trailing slash on the path is compared with the template
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TrailingSlash {
+    /// `/path` and `/path/` both match either template, the one written like the path wins
+    #[default]
+    Ignore,
+    /// The path has to end in a slash exactly when the template does
+    Strict,
+    /// Like `Strict`, but a path differing from its template only by the slash
+    /// resolves to a [`Redirect`](crate::Redirect) to the template's form
+    RedirectToCanonical,
+}
+
+/// Options controlling how request paths are matched against templates
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MatchOptions {
+    /// Percent-decode captured values before handing them to [`Params`](crate::Params)
+    pub decode_params: bool,
+    /// Applies to parameter and star captures. `Reject` is honoured even when `decode_params` is off
+    pub encoded_slash: EncodedSlash,
+    /// How constants are compared, a [`PathRouter`](crate::PathRouter) uses this for routes
+    /// registered without their own setting
+    pub case_sensitivity: CaseSensitivity,
+    /// A trailing catch-all takes the slash along with the rest of the path, so this doesn't apply to it
+    pub trailing_slash: TrailingSlash,
+    /// Applied to the path before anything is matched
+    pub normalization: Normalization,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        MatchOptions {
+            decode_params: true,
+            encoded_slash: EncodedSlash::default(),
+            case_sensitivity: CaseSensitivity::default(),
+            trailing_slash: TrailingSlash::default(),
+            normalization: Normalization::default(),
+        }
+    }
+}
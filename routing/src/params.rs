@@ -1,7 +1,9 @@
 #[cfg(feature = "std")]
 use std::sync::Arc;
 
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
+
+use crate::Id;
 
 use alloc::{borrow::Cow, collections::btree_map::BTreeMap};
 
@@ -53,3 +55,487 @@ impl Params for std::collections::HashMap<Arc<str>, Arc<str>> {
 impl Params for () {
     fn set(&mut self, _key: Cow<'_, str>, _value: Cow<'_, str>) {}
 }
+
+/// A [`Params`] which can refuse values. A refused value abandons the branch being tried,
+/// everything set on the branch is rolled back and matching continues with the next candidate
+pub trait TryParams {
+    /// Returning `false` vetoes the value, which is left unset then
+    fn try_set(&mut self, key: Cow<'_, str>, value: Cow<'_, str>) -> bool;
+
+    /// The state values are set in, usually the number of values set so far. A branch
+    /// which failed is only skipped when it's reached again from the same checkpoint,
+    /// so a veto may depend on anything that changes the checkpoint
+    fn checkpoint(&self) -> usize;
+
+    /// Undo setting the key, which happened at the checkpoint. Values are undone in the
+    /// reverse order they were set
+    fn rollback(&mut self, key: &str, checkpoint: usize);
+}
+
+impl TryParams for Vec<(String, String)> {
+    fn try_set(&mut self, key: Cow<'_, str>, value: Cow<'_, str>) -> bool {
+        self.push((key.into_owned(), value.into_owned()));
+        true
+    }
+
+    fn checkpoint(&self) -> usize {
+        self.len()
+    }
+
+    fn rollback(&mut self, _key: &str, checkpoint: usize) {
+        self.truncate(checkpoint);
+    }
+}
+
+/// Rolling back removes the key, along with a value it had before matching
+impl TryParams for BTreeMap<String, String> {
+    fn try_set(&mut self, key: Cow<'_, str>, value: Cow<'_, str>) -> bool {
+        self.insert(key.into_owned(), value.into_owned());
+        true
+    }
+
+    fn checkpoint(&self) -> usize {
+        self.len()
+    }
+
+    fn rollback(&mut self, key: &str, _checkpoint: usize) {
+        self.remove(key);
+    }
+}
+
+/// Rolling back removes the key, along with a value it had before matching
+#[cfg(feature = "std")]
+impl TryParams for std::collections::HashMap<String, String> {
+    fn try_set(&mut self, key: Cow<'_, str>, value: Cow<'_, str>) -> bool {
+        self.insert(key.into_owned(), value.into_owned());
+        true
+    }
+
+    fn checkpoint(&self) -> usize {
+        self.len()
+    }
+
+    fn rollback(&mut self, key: &str, _checkpoint: usize) {
+        self.remove(key);
+    }
+}
+
+/// Rolling back removes the key, along with a value it had before matching
+#[cfg(feature = "std")]
+impl TryParams for std::collections::HashMap<Arc<str>, Arc<str>> {
+    fn try_set(&mut self, key: Cow<'_, str>, value: Cow<'_, str>) -> bool {
+        self.insert(key.into(), value.into());
+        true
+    }
+
+    fn checkpoint(&self) -> usize {
+        self.len()
+    }
+
+    fn rollback(&mut self, key: &str, _checkpoint: usize) {
+        self.remove(key);
+    }
+}
+
+/// Where captures go while a path is matched. A capture the sink refuses fails the
+/// branch, captures made on a branch which fails further down are removed again with `rollback`
+pub(crate) trait CaptureSink<'s, 'p> {
+    fn capture(&mut self, name: &'s str, value: Cow<'p, str>) -> bool;
+    fn mark(&self) -> usize;
+    fn rollback(&mut self, mark: usize);
+    fn is_captured(&self, name: &str) -> bool;
+
+    /// Failed branches are remembered along with the state, sinks whose vetoes
+    /// depend on earlier captures tell those apart here
+    fn state(&self) -> usize {
+        0
+    }
+
+    /// The path ended at a route with the current captures, returning `false`
+    /// rolls them back and keeps looking
+    fn finish(&mut self, _node: Id) -> bool {
+        true
+    }
+}
+
+impl<'s, 'p> CaptureSink<'s, 'p> for Vec<(&'s str, Cow<'p, str>)> {
+    fn capture(&mut self, name: &'s str, value: Cow<'p, str>) -> bool {
+        self.push((name, value));
+        true
+    }
+
+    fn mark(&self) -> usize {
+        self.len()
+    }
+
+    fn rollback(&mut self, mark: usize) {
+        self.truncate(mark);
+    }
+
+    fn is_captured(&self, name: &str) -> bool {
+        self.iter().any(|(m, _)| *m == name)
+    }
+}
+
+/// Passes captures on as owned values, for matching against a path
+/// which doesn't live as long as the sink expects
+pub(crate) struct OwnedValues<'c, C>(pub &'c mut C);
+
+impl<'c, 's, 'p, 'n, C: CaptureSink<'s, 'p>> CaptureSink<'s, 'n> for OwnedValues<'c, C> {
+    fn capture(&mut self, name: &'s str, value: Cow<'n, str>) -> bool {
+        self.0.capture(name, Cow::Owned(value.into_owned()))
+    }
+
+    fn mark(&self) -> usize {
+        self.0.mark()
+    }
+
+    fn rollback(&mut self, mark: usize) {
+        self.0.rollback(mark);
+    }
+
+    fn is_captured(&self, name: &str) -> bool {
+        self.0.is_captured(name)
+    }
+
+    fn state(&self) -> usize {
+        self.0.state()
+    }
+
+    fn finish(&mut self, node: Id) -> bool {
+        self.0.finish(node)
+    }
+}
+
+type Captures<'s, 'p> = Vec<(&'s str, Cow<'p, str>)>;
+
+/// Records every route the path ends at instead of stopping at the first
+#[derive(Default)]
+pub(crate) struct CollectAll<'s, 'p> {
+    captures: Captures<'s, 'p>,
+    pub found: Vec<(Id, Captures<'s, 'p>)>,
+}
+
+impl<'s, 'p> CaptureSink<'s, 'p> for CollectAll<'s, 'p> {
+    fn capture(&mut self, name: &'s str, value: Cow<'p, str>) -> bool {
+        self.captures.capture(name, value)
+    }
+
+    fn mark(&self) -> usize {
+        self.captures.mark()
+    }
+
+    fn rollback(&mut self, mark: usize) {
+        self.captures.rollback(mark);
+    }
+
+    fn is_captured(&self, name: &str) -> bool {
+        self.captures.is_captured(name)
+    }
+
+    fn finish(&mut self, node: Id) -> bool {
+        self.found.push((node, self.captures.clone()));
+        false
+    }
+}
+
+/// Writes captures straight into a [`TryParams`], remembering the checkpoint
+/// taken before each one so rolling back a capture undoes it there too
+pub(crate) struct TrySink<'s, 'a, P: ?Sized> {
+    params: &'a mut P,
+    captured: Vec<(&'s str, usize)>,
+}
+
+impl<'s, 'a, P: TryParams + ?Sized> TrySink<'s, 'a, P> {
+    pub fn new(params: &'a mut P) -> TrySink<'s, 'a, P> {
+        TrySink {
+            params,
+            captured: Vec::new(),
+        }
+    }
+}
+
+impl<'s, 'a, 'p, P: TryParams + ?Sized> CaptureSink<'s, 'p> for TrySink<'s, 'a, P> {
+    fn capture(&mut self, name: &'s str, value: Cow<'p, str>) -> bool {
+        let checkpoint = self.params.checkpoint();
+        if !self.params.try_set(name.into(), value) {
+            return false;
+        }
+        self.captured.push((name, checkpoint));
+        true
+    }
+
+    fn mark(&self) -> usize {
+        self.captured.len()
+    }
+
+    fn rollback(&mut self, mark: usize) {
+        while self.captured.len() > mark {
+            if let Some((name, checkpoint)) = self.captured.pop() {
+                self.params.rollback(name, checkpoint);
+            }
+        }
+    }
+
+    fn is_captured(&self, name: &str) -> bool {
+        self.captured.iter().any(|(m, _)| *m == name)
+    }
+
+    fn state(&self) -> usize {
+        self.params.checkpoint()
+    }
+}
+
+/// Keeps looking past the routes the path ends at, remembering the first one of the
+/// highest priority. A route with the priority `top` can't be beaten and is taken right away
+pub(crate) struct Ranked<'c, C, F> {
+    captures: &'c mut C,
+    priority: F,
+    top: i32,
+    pub best: Option<(Id, i32)>,
+}
+
+impl<'c, C, F: Fn(Id) -> i32> Ranked<'c, C, F> {
+    pub fn new(captures: &'c mut C, top: i32, priority: F) -> Ranked<'c, C, F> {
+        Ranked {
+            captures,
+            priority,
+            top,
+            best: None,
+        }
+    }
+}
+
+impl<'c, 's, 'p, C: CaptureSink<'s, 'p>, F: Fn(Id) -> i32> CaptureSink<'s, 'p>
+    for Ranked<'c, C, F>
+{
+    fn capture(&mut self, name: &'s str, value: Cow<'p, str>) -> bool {
+        self.captures.capture(name, value)
+    }
+
+    fn mark(&self) -> usize {
+        self.captures.mark()
+    }
+
+    fn rollback(&mut self, mark: usize) {
+        self.captures.rollback(mark);
+    }
+
+    fn is_captured(&self, name: &str) -> bool {
+        self.captures.is_captured(name)
+    }
+
+    fn state(&self) -> usize {
+        self.captures.state()
+    }
+
+    fn finish(&mut self, node: Id) -> bool {
+        if !self.captures.finish(node) {
+            return false;
+        }
+
+        let priority = (self.priority)(node);
+        if priority >= self.top {
+            return true;
+        }
+        if self.best.is_none_or(|(_, best)| priority > best) {
+            self.best = Some((node, priority));
+        }
+        false
+    }
+}
+
+/// Only accepts the path ending at the given node
+pub(crate) struct Only<'c, C> {
+    pub captures: &'c mut C,
+    pub node: Id,
+}
+
+impl<'c, 's, 'p, C: CaptureSink<'s, 'p>> CaptureSink<'s, 'p> for Only<'c, C> {
+    fn capture(&mut self, name: &'s str, value: Cow<'p, str>) -> bool {
+        self.captures.capture(name, value)
+    }
+
+    fn mark(&self) -> usize {
+        self.captures.mark()
+    }
+
+    fn rollback(&mut self, mark: usize) {
+        self.captures.rollback(mark);
+    }
+
+    fn is_captured(&self, name: &str) -> bool {
+        self.captures.is_captured(name)
+    }
+
+    fn state(&self) -> usize {
+        self.captures.state()
+    }
+
+    fn finish(&mut self, node: Id) -> bool {
+        node == self.node && self.captures.finish(node)
+    }
+}
+
+/// Parameters borrowing their names from the router and their values from the path.
+/// The first `N` entries are stored inline, so matching doesn't allocate unless
+/// there are more parameters or a value had to be percent-decoded.
+/// Size `N` with [`PathRouter::max_params`](crate::PathRouter::max_params)
+#[derive(Debug, Clone)]
+pub struct PathParams<'r, 'p, const N: usize = 8> {
+    inline: [Option<(&'r str, Cow<'p, str>)>; N],
+    spilled: Vec<(&'r str, Cow<'p, str>)>,
+    len: usize,
+}
+
+impl<'r, 'p, const N: usize> Default for PathParams<'r, 'p, N> {
+    fn default() -> Self {
+        PathParams::new()
+    }
+}
+
+impl<'r, 'p, const N: usize> PathParams<'r, 'p, N> {
+    pub fn new() -> PathParams<'r, 'p, N> {
+        PathParams {
+            inline: core::array::from_fn(|_| None),
+            spilled: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, name: &'r str, value: Cow<'p, str>) {
+        if self.len < N {
+            self.inline[self.len] = Some((name, value));
+        } else {
+            self.spilled.push((name, value));
+        }
+        self.len += 1;
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        for slot in &mut self.inline[len.min(N)..self.len.min(N)] {
+            *slot = None;
+        }
+        self.spilled.truncate(len.saturating_sub(N));
+        self.len = len;
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Look up a value by name or by position. With repeated names the first one wins
+    pub fn get<I: ParamIndex>(&self, index: I) -> Option<&str> {
+        let idx = index.position(self)?;
+        self.entry(idx).map(|(_, value)| value.as_ref())
+    }
+
+    /// Every value captured under the name
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.iter()
+            .filter(move |(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Name and value of the parameter at the position
+    pub fn entry(&self, idx: usize) -> Option<(&'r str, &Cow<'p, str>)> {
+        if idx >= self.len {
+            return None;
+        }
+        let (name, value) = if idx < N {
+            self.inline[idx].as_ref()?
+        } else {
+            self.spilled.get(idx - N)?
+        };
+        Some((*name, value))
+    }
+
+    /// Names and values in the order they appear in the path
+    pub fn iter(&self) -> impl Iterator<Item = (&'r str, &str)> + '_ {
+        (0..self.len).filter_map(move |idx| self.entry(idx).map(|(k, v)| (k, v.as_ref())))
+    }
+}
+
+impl<'r, 'p, const N: usize> CaptureSink<'r, 'p> for PathParams<'r, 'p, N> {
+    fn capture(&mut self, name: &'r str, value: Cow<'p, str>) -> bool {
+        self.push(name, value);
+        true
+    }
+
+    fn mark(&self) -> usize {
+        self.len
+    }
+
+    fn rollback(&mut self, mark: usize) {
+        self.truncate(mark);
+    }
+
+    fn is_captured(&self, name: &str) -> bool {
+        self.iter().any(|(m, _)| m == name)
+    }
+}
+
+/// A parameter name or position, see [`PathParams::get`]
+pub trait ParamIndex {
+    fn position<const N: usize>(&self, params: &PathParams<'_, '_, N>) -> Option<usize>;
+}
+
+impl ParamIndex for usize {
+    fn position<const N: usize>(&self, params: &PathParams<'_, '_, N>) -> Option<usize> {
+        (*self < params.len()).then_some(*self)
+    }
+}
+
+impl ParamIndex for &str {
+    fn position<const N: usize>(&self, params: &PathParams<'_, '_, N>) -> Option<usize> {
+        params.iter().position(|(name, _)| name == *self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_path_params() {
+        let mut params = PathParams::<'_, '_, 2>::new();
+        params.push("owner", "kildevaeld".into());
+        params.push("repo", "router-rs".into());
+        params.push("owner", "other".into());
+
+        assert_eq!(params.len(), 3);
+        assert_eq!(params.get("owner"), Some("kildevaeld"));
+        assert_eq!(params.get(1), Some("router-rs"));
+        assert_eq!(params.get(2), Some("other"));
+        assert_eq!(params.get(3), None);
+        assert_eq!(
+            params.get_all("owner").collect::<Vec<_>>(),
+            ["kildevaeld", "other"]
+        );
+        assert_eq!(
+            params.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["owner", "repo", "owner"]
+        );
+
+        params.truncate(1);
+        assert_eq!(params.len(), 1);
+        assert_eq!(params.get("repo"), None);
+        params.push("repo", "routing".into());
+        assert_eq!(params.get(1), Some("routing"));
+
+        params.clear();
+        assert!(params.is_empty());
+        assert_eq!(params.get(0), None);
+    }
+}
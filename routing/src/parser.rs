@@ -1,63 +1,438 @@
-use alloc::vec::Vec;
-use udled::{
-    any,
-    tokenizers::{AlphaNumeric, Opt, Puntuated},
-    AsChar, AsSlice, AsStr, Buffer, Input, Tokenizer, TokenizerExt, EOF,
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+use crate::{
+    error::{RouteSyntaxError, SyntaxErrorKind},
+    percent::{decode, is_constant_char, is_escapable, unescape},
+    Constraint, Dialect, Segment, Segments,
 };
-use udled_tokenizers::Ident;
 
-use crate::{Segment, Segments};
+const EXPECTED_SEGMENT: &[&str] = &["constant", "`:`", "`*`"];
+const EXPECTED_BRACE_SEGMENT: &[&str] = &["constant", "`{`"];
+const EXPECTED_SEPARATOR: &[&str] = &["`/`", "end of template"];
+const EXPECTED_ESCAPE: &[&str] = &["`:`", "`*`", "`(`", "`)`", "`\\`"];
+
+pub fn parse(input: &str) -> Result<Segments<'_>, RouteSyntaxError> {
+    parse_with(Dialect::Native, input)
+}
 
-pub fn parse<'a>(input: &'a str) -> Result<Segments<'a>, udled::Error> {
-    let mut input = Input::new(input);
+/// Parse a template written in the syntax of another router
+pub fn parse_with(dialect: Dialect, input: &str) -> Result<Segments<'_>, RouteSyntaxError> {
+    Parser::new(input, dialect).parse()
+}
 
-    input.eat(Opt::new('/'))?;
+struct Parser<'a> {
+    input: &'a str,
+    dialect: Dialect,
+    pos: usize,
+    names: Vec<&'a str>,
+}
 
-    if input.is(EOF) {
-        return Ok(Segments::default());
+impl<'a> Parser<'a> {
+    fn new(input: &'a str, dialect: Dialect) -> Parser<'a> {
+        Parser {
+            input,
+            dialect,
+            pos: 0,
+            names: Vec::new(),
+        }
     }
 
-    let mut segments = input
-        .parse(Puntuated::new(SegmentParser, '/').optional())?
-        .map(|m| m.into_items().collect::<Vec<_>>())
-        .unwrap_or_default();
+    fn parse(mut self) -> Result<Segments<'a>, RouteSyntaxError> {
+        self.eat('/');
+
+        let mut segments = Vec::new();
+
+        while self.peek().is_some() {
+            let segment = match self.dialect {
+                Dialect::Native => self.native_segment()?,
+                Dialect::Express => self.express_segment()?,
+                Dialect::Brace | Dialect::OpenApi => self.brace_segment()?,
+            };
+            segments.push(segment);
 
-    input.eat('/'.optional())?;
+            match self.peek() {
+                None => break,
+                Some('/') => {
+                    self.pos += 1;
+                    // A trailing slash is kept as an empty constant
+                    if self.peek().is_none() {
+                        segments.push(Segment::Constant("".into()));
+                    }
+                }
+                Some(ch) => return Err(self.unexpected(ch, EXPECTED_SEPARATOR)),
+            }
+        }
 
-    if input.is('*') {
-        let (_, name) = input.parse(('*', Ident))?;
-        segments.push(Segment::Star(name.value.into()));
+        Ok(segments.into())
     }
 
-    Ok(segments.into())
-}
+    /// `*name`, or parts followed by an optional `?`
+    fn native_segment(&mut self) -> Result<Segment<'a>, RouteSyntaxError> {
+        let segment = if self.eat('*') {
+            Segment::Star(self.name(self.pos - 1)?.into())
+        } else if self.peek() == Some('(') {
+            Segment::Alternation(None, self.alternatives()?)
+        } else {
+            self.parts()?
+        };
+
+        if self.eat('?') {
+            Ok(Segment::optional(segment))
+        } else {
+            Ok(segment)
+        }
+    }
 
-struct SegmentParser;
+    /// Parts where a lone parameter may end with a `?`, `*` or `+` modifier
+    fn express_segment(&mut self) -> Result<Segment<'a>, RouteSyntaxError> {
+        let segment = self.parts()?;
 
-impl<'input, B> Tokenizer<'input, B> for SegmentParser
-where
-    B: Buffer<'input>,
-    B::Item: AsChar,
-    B::Source: AsSlice<'input>,
-    <B::Source as AsSlice<'input>>::Slice: AsStr<'input>,
-{
-    type Token = Segment<'input>;
+        let modifier = match self.peek() {
+            Some(ch) if matches!(ch, '?' | '*' | '+') => ch,
+            _ => return Ok(segment),
+        };
 
-    fn to_token<'a>(
-        &self,
-        reader: &mut udled::Reader<'_, 'input, B>,
-    ) -> Result<Self::Token, udled::Error> {
-        if reader.is(':') {
-            let (_, ident) = reader.parse((':', Ident))?;
-            Ok(Segment::Parameter(ident.value.as_str().into()))
+        let segment = match (segment, modifier) {
+            (Segment::Parameter(name, constraint), '?') => {
+                Segment::optional(Segment::Parameter(name, constraint))
+            }
+            (Segment::Parameter(name, None), '*') => Segment::optional(Segment::Star(name)),
+            (Segment::Parameter(name, None), '+') => Segment::Star(name),
+            _ => return Err(self.unexpected(modifier, EXPECTED_SEPARATOR)),
+        };
+
+        self.pos += 1;
+
+        Ok(segment)
+    }
+
+    /// `{*name}` or parts
+    fn brace_segment(&mut self) -> Result<Segment<'a>, RouteSyntaxError> {
+        if self.dialect == Dialect::Brace && self.input[self.pos..].starts_with("{*") {
+            let start = self.pos;
+            self.pos += 2;
+            let name = self.name(start)?;
+            self.close_brace()?;
+            return Ok(Segment::Star(name.into()));
+        }
+
+        self.parts()
+    }
+
+    /// Constants and parameters making up one path component
+    fn parts(&mut self) -> Result<Segment<'a>, RouteSyntaxError> {
+        let mut parts: Vec<Segment<'a>> = Vec::new();
+
+        loop {
+            let start = self.pos;
+
+            let part = match self.peek() {
+                Some(ch)
+                    if self.dialect == Dialect::Express
+                        && matches!(ch, '?' | '*' | '+')
+                        && matches!(parts.last(), Some(Segment::Parameter(..))) =>
+                {
+                    break
+                }
+                Some(':') if matches!(self.dialect, Dialect::Native | Dialect::Express) => {
+                    self.parameter()?
+                }
+                Some('{') if !self.is_constant_start() => self.brace_parameter()?,
+                Some(_) if self.is_constant_start() => self.constant()?,
+                _ => break,
+            };
+
+            if let (Some(Segment::Parameter(..)), Segment::Parameter(..)) = (parts.last(), &part) {
+                return Err(self.error(SyntaxErrorKind::AdjacentParameters, start..start + 1, &[]));
+            }
+
+            // Alternations take up the whole component
+            if (matches!(part, Segment::Alternation(..)) && !parts.is_empty())
+                || matches!(parts.last(), Some(Segment::Alternation(..)))
+            {
+                let ch = self.input[start..].chars().next().unwrap_or_default();
+                return Err(self.error(
+                    SyntaxErrorKind::UnexpectedChar(ch),
+                    start..start + ch.len_utf8(),
+                    EXPECTED_SEPARATOR,
+                ));
+            }
+
+            parts.push(part);
+        }
+
+        match parts.len() {
+            0 => {
+                let ch = self.peek().unwrap_or_default();
+                let expected = match self.dialect {
+                    Dialect::Native | Dialect::Express => EXPECTED_SEGMENT,
+                    Dialect::Brace | Dialect::OpenApi => EXPECTED_BRACE_SEGMENT,
+                };
+                Err(self.unexpected(ch, expected))
+            }
+            1 => Ok(parts.remove(0)),
+            _ => Ok(Segment::Composite(parts)),
+        }
+    }
+
+    /// `:name` followed by `<constraint>`, or `(pattern)` for express templates
+    fn parameter(&mut self) -> Result<Segment<'a>, RouteSyntaxError> {
+        let start = self.pos;
+        self.pos += 1;
+        let name = self.name(start)?;
+        let constraint = match (self.dialect, self.peek()) {
+            (Dialect::Native, Some('(')) => {
+                return Ok(Segment::Alternation(
+                    Some(name.into()),
+                    self.alternatives()?,
+                ))
+            }
+            (Dialect::Native, Some('<')) => Some(self.constraint('<', '>', Constraint::parse)?),
+            (Dialect::Express, Some('(')) => {
+                Some(self.constraint('(', ')', Constraint::pattern)?)
+            }
+            _ => None,
+        };
+        Ok(Segment::Parameter(name.into(), constraint))
+    }
+
+    /// `(users|people)`, a list of constants
+    fn alternatives(&mut self) -> Result<Vec<Cow<'a, str>>, RouteSyntaxError> {
+        self.pos += 1;
+
+        let mut alternatives = Vec::new();
+
+        loop {
+            match self.peek() {
+                Some(_) if self.is_constant_start() => alternatives.push(self.constant_text()?),
+                Some(ch) => return Err(self.unexpected(ch, &["constant"])),
+                None => {
+                    return Err(self.error(
+                        SyntaxErrorKind::UnexpectedEnd,
+                        self.pos..self.pos,
+                        &["constant"],
+                    ))
+                }
+            }
+
+            match self.peek() {
+                Some('|') => self.pos += 1,
+                Some(')') => {
+                    self.pos += 1;
+                    return Ok(alternatives);
+                }
+                Some(ch) => return Err(self.unexpected(ch, &["`|`", "`)`"])),
+                None => {
+                    return Err(self.error(
+                        SyntaxErrorKind::UnexpectedEnd,
+                        self.pos..self.pos,
+                        &["`|`", "`)`"],
+                    ))
+                }
+            }
+        }
+    }
+
+    /// `{name}`
+    fn brace_parameter(&mut self) -> Result<Segment<'a>, RouteSyntaxError> {
+        let start = self.pos;
+        self.pos += 1;
+        let name = self.name(start)?;
+        self.close_brace()?;
+        Ok(Segment::Parameter(name.into(), None))
+    }
+
+    fn close_brace(&mut self) -> Result<(), RouteSyntaxError> {
+        match self.peek() {
+            Some('}') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(ch) => Err(self.unexpected(ch, &["`}`"])),
+            None => Err(self.error(SyntaxErrorKind::UnexpectedEnd, self.pos..self.pos, &["`}`"])),
+        }
+    }
+
+    /// The name following a `:`, `*` or `{` at `sigil`
+    fn name(&mut self, sigil: usize) -> Result<&'a str, RouteSyntaxError> {
+        let start = self.pos;
+
+        match self.peek() {
+            Some(ch) if ch.is_alphabetic() || ch == '_' => self.pos += ch.len_utf8(),
+            _ => return Err(self.error(SyntaxErrorKind::EmptyParameterName, sigil..self.pos, &[])),
+        }
+
+        while let Some(ch) = self.peek() {
+            if !(ch.is_alphanumeric() || ch == '_') {
+                break;
+            }
+            self.pos += ch.len_utf8();
+        }
+
+        let name = &self.input[start..self.pos];
+
+        if self.names.contains(&name) {
+            return Err(self.error(
+                SyntaxErrorKind::DuplicateParameterName(name.into()),
+                start..self.pos,
+                &[],
+            ));
+        }
+
+        self.names.push(name);
+
+        Ok(name)
+    }
+
+    /// Everything up to the closing delimiter, nested pairs and `\` escapes are kept in the body
+    fn constraint(
+        &mut self,
+        open: char,
+        close: char,
+        parse: fn(&'a str) -> Result<Constraint<'a>, crate::ConstraintError>,
+    ) -> Result<Constraint<'a>, RouteSyntaxError> {
+        let start = self.pos;
+        self.pos += 1;
+
+        let mut depth = 0usize;
+        let mut chars = self.input[self.pos..].char_indices();
+
+        let end = loop {
+            let Some((idx, ch)) = chars.next() else {
+                return Err(self.error(
+                    SyntaxErrorKind::UnterminatedConstraint,
+                    start..self.input.len(),
+                    if close == '>' { &["`>`"] } else { &["`)`"] },
+                ));
+            };
+
+            match ch {
+                ch if ch == close && depth == 0 => break self.pos + idx,
+                ch if ch == close => depth -= 1,
+                ch if ch == open => depth += 1,
+                '\\' => {
+                    chars.next();
+                }
+                _ => {}
+            }
+        };
+
+        let body = &self.input[self.pos..end];
+        let constraint = parse(body).map_err(|err| {
+            self.error(SyntaxErrorKind::InvalidConstraint(err), self.pos..end, &[])
+        })?;
+
+        self.pos = end + 1;
+
+        Ok(constraint)
+    }
+
+    fn is_constant_start(&self) -> bool {
+        let rest = &self.input[self.pos..];
+        match rest.chars().next() {
+            Some(ch) if self.is_literal(ch) => true,
+            _ => {
+                self.dialect == Dialect::Brace && (rest.starts_with("{{") || rest.starts_with("}}"))
+            }
+        }
+    }
+
+    /// Chars which are taken as they are in a constant
+    fn is_literal(&self, ch: char) -> bool {
+        match self.dialect {
+            Dialect::Native | Dialect::Express => is_constant_char(ch),
+            Dialect::Brace | Dialect::OpenApi => {
+                (is_constant_char(ch) && ch != '\\') || matches!(ch, ':' | '*' | '(' | ')')
+            }
+        }
+    }
+
+    fn constant(&mut self) -> Result<Segment<'a>, RouteSyntaxError> {
+        Ok(Segment::Constant(self.constant_text()?))
+    }
+
+    fn constant_text(&mut self) -> Result<Cow<'a, str>, RouteSyntaxError> {
+        let start = self.pos;
+
+        while let Some(ch) = self.peek() {
+            if ch == '%' {
+                let escape = self.input[self.pos + 1..]
+                    .bytes()
+                    .take(2)
+                    .take_while(|m| m.is_ascii_hexdigit())
+                    .count();
+                if escape != 2 {
+                    return Err(self.error(
+                        SyntaxErrorKind::InvalidEscape,
+                        self.pos..self.pos + 1 + escape,
+                        &["two hex digits"],
+                    ));
+                }
+                self.pos += 3;
+            } else if ch == '\\' && self.is_literal(ch) {
+                match self.input[self.pos + 1..].chars().next() {
+                    Some(next) if is_escapable(next) => self.pos += 1 + next.len_utf8(),
+                    next => {
+                        return Err(self.error(
+                            SyntaxErrorKind::InvalidEscape,
+                            self.pos..self.pos + 1 + next.map(char::len_utf8).unwrap_or_default(),
+                            EXPECTED_ESCAPE,
+                        ))
+                    }
+                }
+            } else if self.is_literal(ch) {
+                self.pos += ch.len_utf8();
+            } else if self.is_constant_start() {
+                // Doubled brace
+                self.pos += 2;
+            } else {
+                break;
+            }
+        }
+
+        let raw = &self.input[start..self.pos];
+        let unescaped = match self.dialect {
+            Dialect::Native | Dialect::Express => unescape(raw),
+            Dialect::Brace if raw.contains("{{") || raw.contains("}}") => {
+                Cow::Owned(raw.replace("{{", "{").replace("}}", "}"))
+            }
+            Dialect::Brace | Dialect::OpenApi => Cow::Borrowed(raw),
+        };
+
+        Ok(match unescaped {
+            Cow::Borrowed(constant) => decode(constant, false),
+            Cow::Owned(constant) => Cow::Owned(String::from(decode(&constant, false))),
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        if self.peek() == Some(ch) {
+            self.pos += ch.len_utf8();
+            true
         } else {
-            let path = reader.parse(any!(AlphaNumeric, '_', '.', '-', '~').many().slice())?;
-            Ok(Segment::Constant(path.value.as_str().into()))
+            false
         }
     }
 
-    fn peek(&self, reader: &mut udled::Reader<'_, 'input, B>) -> bool {
-        reader.is(':') || reader.is(Ident)
+    fn unexpected(&self, ch: char, expected: &'static [&'static str]) -> RouteSyntaxError {
+        self.error(
+            SyntaxErrorKind::UnexpectedChar(ch),
+            self.pos..self.pos + ch.len_utf8(),
+            expected,
+        )
+    }
+
+    fn error(
+        &self,
+        kind: SyntaxErrorKind,
+        span: core::ops::Range<usize>,
+        expected: &'static [&'static str],
+    ) -> RouteSyntaxError {
+        RouteSyntaxError::new(kind, span, expected, self.input)
     }
 }
 
@@ -65,7 +440,7 @@ where
 mod test {
     use super::*;
 
-    use alloc::vec;
+    use alloc::{string::ToString, vec};
 
     #[test]
     fn test_parse() {
@@ -80,6 +455,15 @@ mod test {
             parse("/path").expect("parse constant"),
             vec![Segment::Constant("path".into())].into()
         );
+        assert_eq!(
+            parse("/path/").expect("parse trailing slash"),
+            vec![
+                Segment::Constant("path".into()),
+                Segment::Constant("".into())
+            ]
+            .into()
+        );
+        assert_eq!(parse("/path/").expect("parse").to_string(), "/path/");
         assert_eq!(
             parse("/path/subpath").expect("parse constant"),
             vec![
@@ -92,7 +476,7 @@ mod test {
             parse("/path/:subpath").expect("parse parameter"),
             vec![
                 Segment::Constant("path".into()),
-                Segment::Parameter("subpath".into())
+                Segment::Parameter("subpath".into(), None)
             ]
             .into()
         );
@@ -100,8 +484,8 @@ mod test {
             parse("/api/:type/:id").expect("parse parameter"),
             vec![
                 Segment::Constant("api".into()),
-                Segment::Parameter("type".into()),
-                Segment::Parameter("id".into())
+                Segment::Parameter("type".into(), None),
+                Segment::Parameter("id".into(), None)
             ]
             .into()
         );
@@ -109,8 +493,8 @@ mod test {
             parse("/api/:type/:id/admin").expect("parse parameter"),
             vec![
                 Segment::Constant("api".into()),
-                Segment::Parameter("type".into()),
-                Segment::Parameter("id".into()),
+                Segment::Parameter("type".into(), None),
+                Segment::Parameter("id".into(), None),
                 Segment::Constant("admin".into())
             ]
             .into()
@@ -133,10 +517,351 @@ mod test {
         assert_eq!(
             parse("/:path/*all").expect("parse parameter"),
             vec![
-                Segment::Parameter("path".into()),
+                Segment::Parameter("path".into(), None),
                 Segment::Star("all".into())
             ]
             .into()
         );
     }
+
+    #[test]
+    fn test_parse_constraint() {
+        assert_eq!(
+            parse("/users/:id<u64>").expect("parse constraint"),
+            vec![
+                Segment::Constant("users".into()),
+                Segment::Parameter(
+                    "id".into(),
+                    Some(Constraint::Integer(crate::IntKind::U64, None))
+                )
+            ]
+            .into()
+        );
+
+        assert_eq!(
+            parse("/v/:n<int(1..=9)>/info")
+                .expect("parse constraint")
+                .to_string(),
+            "/v/:n<int(1..=9)>/info"
+        );
+
+        assert!(parse("/v/:n<int(9..1)>").is_err());
+        assert!(parse("/v/:n<u64").is_err());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_parse_pattern_constraint() {
+        assert_eq!(
+            parse("/posts/:slug<[a-z0-9-]+>").expect("parse pattern"),
+            vec![
+                Segment::Constant("posts".into()),
+                Segment::Parameter(
+                    "slug".into(),
+                    Some(Constraint::Pattern("[a-z0-9-]+".into()))
+                )
+            ]
+            .into()
+        );
+        assert_eq!(
+            parse("/:name<(?P<first>[a-z]+)>")
+                .expect("parse nested")
+                .to_string(),
+            "/:name<(?P<first>[a-z]+)>"
+        );
+    }
+
+    #[test]
+    fn test_parse_optional() {
+        assert_eq!(
+            parse("/:lang?/docs").expect("parse optional"),
+            vec![
+                Segment::optional(Segment::Parameter("lang".into(), None)),
+                Segment::Constant("docs".into())
+            ]
+            .into()
+        );
+        assert_eq!(
+            parse("/files/*rest?").expect("parse optional star"),
+            vec![
+                Segment::Constant("files".into()),
+                Segment::optional(Segment::Star("rest".into()))
+            ]
+            .into()
+        );
+        assert_eq!(
+            parse("/:lang?/docs").expect("parse").to_string(),
+            "/:lang?/docs"
+        );
+    }
+
+    #[test]
+    fn test_parse_composite() {
+        assert_eq!(
+            parse("/files/:name.:ext").expect("parse composite"),
+            vec![
+                Segment::Constant("files".into()),
+                Segment::Composite(vec![
+                    Segment::Parameter("name".into(), None),
+                    Segment::Constant(".".into()),
+                    Segment::Parameter("ext".into(), None),
+                ])
+            ]
+            .into()
+        );
+        assert_eq!(
+            parse("/v:version/api").expect("parse composite"),
+            vec![
+                Segment::Composite(vec![
+                    Segment::Constant("v".into()),
+                    Segment::Parameter("version".into(), None),
+                ]),
+                Segment::Constant("api".into())
+            ]
+            .into()
+        );
+        assert_eq!(
+            parse("/@:user").expect("parse composite").to_string(),
+            "/@:user"
+        );
+        assert!(parse("/:a:b").is_err());
+    }
+
+    #[test]
+    fn test_parse_inner_star() {
+        assert_eq!(
+            parse("/repos/*path/blob/:rev").expect("parse inner star"),
+            vec![
+                Segment::Constant("repos".into()),
+                Segment::Star("path".into()),
+                Segment::Constant("blob".into()),
+                Segment::Parameter("rev".into(), None)
+            ]
+            .into()
+        );
+        assert_eq!(
+            parse("/buckets/:b/*key/acl")
+                .expect("parse inner star")
+                .to_string(),
+            "/buckets/:b/*key/acl"
+        );
+    }
+
+    #[test]
+    fn test_parse_encoded() {
+        assert_eq!(
+            parse("/hello%20world/café/a+b!$@").expect("parse encoded"),
+            vec![
+                Segment::Constant("hello world".into()),
+                Segment::Constant("café".into()),
+                Segment::Constant("a+b!$@".into()),
+            ]
+            .into()
+        );
+        assert_eq!(
+            parse("/hello%20world/100%25")
+                .expect("parse encoded")
+                .to_string(),
+            "/hello%20world/100%25"
+        );
+        assert!(parse("/100%").is_err());
+        assert!(parse("/hello world").is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse("/users/:").unwrap_err();
+        assert!(matches!(err.kind(), SyntaxErrorKind::EmptyParameterName));
+        assert_eq!(err.span(), 7..8);
+
+        let err = parse("/:id/posts/:id").unwrap_err();
+        assert!(
+            matches!(err.kind(), SyntaxErrorKind::DuplicateParameterName(name) if name == "id")
+        );
+        assert_eq!(err.span(), 12..14);
+        assert!(parse("/:rest/*rest").is_err());
+
+        let err = parse("/hello world").unwrap_err();
+        assert!(matches!(err.kind(), SyntaxErrorKind::UnexpectedChar(' ')));
+        assert_eq!(err.offset(), 6);
+        assert_eq!(err.expected(), EXPECTED_SEPARATOR);
+
+        let err = parse("/a//b").unwrap_err();
+        assert!(matches!(err.kind(), SyntaxErrorKind::UnexpectedChar('/')));
+        assert_eq!(err.offset(), 3);
+
+        let err = parse("/v/:n<u64").unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            SyntaxErrorKind::UnterminatedConstraint
+        ));
+        assert_eq!(err.span(), 5..9);
+
+        let err = parse("/v/:n<int(9..1)>").unwrap_err();
+        assert!(matches!(err.kind(), SyntaxErrorKind::InvalidConstraint(_)));
+        assert_eq!(err.span(), 6..15);
+
+        let err = parse("/100%2").unwrap_err();
+        assert!(matches!(err.kind(), SyntaxErrorKind::InvalidEscape));
+        assert_eq!(err.span(), 4..6);
+
+        assert!(matches!(
+            parse("/:a:b").unwrap_err().kind(),
+            SyntaxErrorKind::AdjacentParameters
+        ));
+
+        assert_eq!(
+            parse("/files/:").unwrap_err().to_string(),
+            "empty parameter name at offset 7\n  /files/:\n         ^"
+        );
+    }
+
+    #[test]
+    fn test_parse_escaped() {
+        assert_eq!(
+            parse(r"/ns\:core/item/glob\*").expect("parse escaped"),
+            vec![
+                Segment::Constant("ns:core".into()),
+                Segment::Constant("item".into()),
+                Segment::Constant("glob*".into()),
+            ]
+            .into()
+        );
+        assert_eq!(
+            parse(r"/v\::version").expect("parse escaped"),
+            vec![Segment::Composite(vec![
+                Segment::Constant("v:".into()),
+                Segment::Parameter("version".into(), None),
+            ])]
+            .into()
+        );
+
+        for template in [
+            r"/ns\:core/item",
+            r"/glob\*/:name\(1\)",
+            r"/back\\slash%20x",
+            "/a%3Fb",
+        ]
+        .iter()
+        {
+            let segments = parse(template).expect("parse");
+            assert_eq!(segments.to_string(), *template);
+            assert_eq!(parse(&segments.to_string()).expect("reparse"), segments);
+        }
+
+        let err = parse(r"/a\b").unwrap_err();
+        assert!(matches!(err.kind(), SyntaxErrorKind::InvalidEscape));
+        assert_eq!(err.span(), 2..4);
+        assert!(parse("/a\\").is_err());
+    }
+
+    #[test]
+    fn test_parse_brace() {
+        let expected: Segments = vec![
+            Segment::Constant("users".into()),
+            Segment::Parameter("id".into(), None),
+            Segment::Composite(vec![
+                Segment::Parameter("name".into(), None),
+                Segment::Constant(".".into()),
+                Segment::Parameter("ext".into(), None),
+            ]),
+            Segment::Star("rest".into()),
+        ]
+        .into();
+
+        assert_eq!(
+            parse_with(Dialect::Brace, "/users/{id}/{name}.{ext}/{*rest}").expect("parse brace"),
+            expected
+        );
+        assert_eq!(
+            parse_with(Dialect::Brace, "/ns:core/{{literal}}").expect("parse brace"),
+            vec![
+                Segment::Constant("ns:core".into()),
+                Segment::Constant("{literal}".into())
+            ]
+            .into()
+        );
+
+        assert!(parse_with(Dialect::Brace, "/{*rest}.json").is_err());
+        assert!(parse_with(Dialect::Brace, "/{id").is_err());
+        assert!(parse_with(Dialect::Brace, "/{a}{b}").is_err());
+        assert!(parse_with(Dialect::OpenApi, "/files/{*rest}").is_err());
+        assert_eq!(
+            parse_with(Dialect::OpenApi, "/users/{userId}/posts").expect("parse openapi"),
+            vec![
+                Segment::Constant("users".into()),
+                Segment::Parameter("userId".into(), None),
+                Segment::Constant("posts".into())
+            ]
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_parse_express() {
+        assert_eq!(
+            parse_with(Dialect::Express, "/users/:id/:lang?/:rest*").expect("parse express"),
+            vec![
+                Segment::Constant("users".into()),
+                Segment::Parameter("id".into(), None),
+                Segment::optional(Segment::Parameter("lang".into(), None)),
+                Segment::optional(Segment::Star("rest".into())),
+            ]
+            .into()
+        );
+        assert_eq!(
+            parse_with(Dialect::Express, "/files/:path+").expect("parse express"),
+            parse("/files/*path").expect("parse")
+        );
+        assert_eq!(
+            parse_with(Dialect::Express, "/:name.:ext").expect("parse express"),
+            parse("/:name.:ext").expect("parse")
+        );
+        assert!(parse_with(Dialect::Express, "/:a.json?").is_err());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_parse_express_pattern() {
+        assert_eq!(
+            parse_with(Dialect::Express, r"/users/:id(\d+)").expect("parse express"),
+            vec![
+                Segment::Constant("users".into()),
+                Segment::Parameter("id".into(), Some(Constraint::Pattern(r"\d+".into()))),
+            ]
+            .into()
+        );
+        assert!(parse_with(Dialect::Express, r"/users/:id(\d+").is_err());
+    }
+
+    #[test]
+    fn test_parse_alternation() {
+        assert_eq!(
+            parse("/:kind(users|people)/:id").expect("parse alternation"),
+            vec![
+                Segment::named_alternation("kind", ["users", "people"].iter().copied()),
+                Segment::Parameter("id".into(), None),
+            ]
+            .into()
+        );
+        assert_eq!(
+            parse("/(about|info%20page)?").expect("parse alternation"),
+            vec![Segment::optional(Segment::alternation(
+                ["about", "info page"].iter().copied()
+            ))]
+            .into()
+        );
+        assert_eq!(
+            parse("/:kind(users|people)/(a|b%7C)")
+                .expect("parse alternation")
+                .to_string(),
+            "/:kind(users|people)/(a|b%7C)"
+        );
+
+        assert!(parse("/v:kind(a|b)").is_err());
+        assert!(parse("/:kind(a|b).json").is_err());
+        assert!(parse("/:kind(a|)").is_err());
+        assert!(parse("/:kind(a|b").is_err());
+        assert!(parse("/:kind(a|b)/:kind").is_err());
+    }
 }
@@ -0,0 +1,302 @@
+use core::{fmt, ops::Range};
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+/// The components of a request path. A leading slash is skipped and a trailing
+/// slash doesn't start another component, every other slash does, so `/a//b`
+/// is `a`, `` and `b`. Normalize the path with a [`Normalization`] first
+/// to get rid of empty and dot components.
+#[derive(Debug, Clone)]
+pub struct PathSegments<'a> {
+    ranges: Ranges<'a>,
+}
+
+impl<'a> PathSegments<'a> {
+    pub fn new(path: &'a str) -> PathSegments<'a> {
+        PathSegments {
+            ranges: Ranges::new(path),
+        }
+    }
+
+    /// Byte ranges of the components in the path
+    pub(crate) fn ranges(self) -> Ranges<'a> {
+        self.ranges
+    }
+}
+
+impl<'a> Iterator for PathSegments<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let range = self.ranges.next()?;
+        Some(&self.ranges.path[range])
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Ranges<'a> {
+    path: &'a str,
+    pos: usize,
+}
+
+impl<'a> Ranges<'a> {
+    fn new(path: &'a str) -> Ranges<'a> {
+        let pos = if path.starts_with('/') { 1 } else { 0 };
+        Ranges { path, pos }
+    }
+}
+
+impl<'a> Iterator for Ranges<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.path.len() {
+            return None;
+        }
+
+        let start = self.pos;
+        let end = match self.path[start..].find('/') {
+            Some(idx) => start + idx,
+            None => self.path.len(),
+        };
+        self.pos = end + 1;
+
+        Some(start..end)
+    }
+}
+
+/// Whether a path ends in a slash, the root path doesn't
+pub(crate) fn has_trailing_slash(path: &str) -> bool {
+    path.len() > 1 && path.ends_with('/')
+}
+
+/// What to do with empty components, as in `/a//b`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EmptySegments {
+    /// Empty components stay, only a star matches them
+    #[default]
+    Keep,
+    /// `/a//b` is treated as `/a/b`
+    Collapse,
+    /// Paths with empty components don't match
+    Reject,
+}
+
+/// How a request path is cleaned up before matching. Nothing is changed by default
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Normalization {
+    /// Remove `.` and `..` components as described in RFC 3986, section 5.2.4.
+    /// Encoded dots (`%2E`) count as dots
+    pub remove_dot_segments: bool,
+    pub empty_segments: EmptySegments,
+    /// Fail on a `..` which climbs above the root instead of dropping it
+    pub reject_traversal: bool,
+}
+
+impl Normalization {
+    /// Normalize a path, it's only copied when something had to change
+    pub fn apply<'a>(&self, path: &'a str) -> Result<Cow<'a, str>, NormalizeError> {
+        if *self == Normalization::default() {
+            return Ok(Cow::Borrowed(path));
+        }
+
+        let mut kept: Vec<&str> = Vec::new();
+        let mut changed = false;
+        let mut depth = 0usize;
+        let mut trailing = has_trailing_slash(path);
+
+        let mut segments = PathSegments::new(path).peekable();
+        while let Some(segment) = segments.next() {
+            let last = segments.peek().is_none();
+
+            if segment.is_empty() {
+                match self.empty_segments {
+                    EmptySegments::Keep => kept.push(segment),
+                    EmptySegments::Collapse => changed = true,
+                    EmptySegments::Reject => return Err(NormalizeError::EmptySegment),
+                }
+                continue;
+            }
+
+            match dots(segment) {
+                1 => {
+                    if self.remove_dot_segments {
+                        changed = true;
+                        trailing |= last;
+                    } else {
+                        kept.push(segment);
+                    }
+                }
+                2 => {
+                    if depth == 0 && self.reject_traversal {
+                        return Err(NormalizeError::Traversal);
+                    }
+                    depth = depth.saturating_sub(1);
+
+                    if self.remove_dot_segments {
+                        // Empty components kept before `..` don't count as a level
+                        while kept.last().map(|m| m.is_empty()).unwrap_or(false) {
+                            kept.pop();
+                        }
+                        kept.pop();
+                        changed = true;
+                        trailing |= last;
+                    } else {
+                        kept.push(segment);
+                    }
+                }
+                _ => {
+                    depth += 1;
+                    kept.push(segment);
+                }
+            }
+        }
+
+        if !changed {
+            return Ok(Cow::Borrowed(path));
+        }
+
+        let mut normalized = String::with_capacity(path.len());
+        for segment in &kept {
+            normalized.push('/');
+            normalized.push_str(segment);
+        }
+        if trailing && !kept.is_empty() {
+            normalized.push('/');
+        }
+        if normalized.is_empty() {
+            normalized.push('/');
+        }
+
+        Ok(Cow::Owned(normalized))
+    }
+}
+
+// 1 for `.`, 2 for `..`, with dots possibly percent-encoded, 0 otherwise
+fn dots(segment: &str) -> usize {
+    let mut rest = segment;
+    let mut count = 0;
+    while !rest.is_empty() {
+        rest = if let Some(rest) = rest.strip_prefix('.') {
+            rest
+        } else if rest
+            .as_bytes()
+            .get(..3)
+            .is_some_and(|m| m.eq_ignore_ascii_case(b"%2e"))
+        {
+            &rest[3..]
+        } else {
+            return 0;
+        };
+        count += 1;
+    }
+
+    if count > 2 {
+        0
+    } else {
+        count
+    }
+}
+
+/// Why a path was refused by its [`Normalization`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizeError {
+    EmptySegment,
+    Traversal,
+}
+
+impl fmt::Display for NormalizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NormalizeError::EmptySegment => f.write_str("empty path segment"),
+            NormalizeError::Traversal => f.write_str("path climbs above the root"),
+        }
+    }
+}
+
+impl core::error::Error for NormalizeError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use alloc::{string::ToString, vec, vec::Vec};
+
+    macro_rules! segments {
+        ($url: literal => $($segs: literal),*) => {
+            assert_eq!(
+                PathSegments::new($url).map(|m| m.to_string()).collect::<Vec<_>>(),
+                vec![$($segs.to_string()),*]
+            );
+
+        };
+        ($url: literal) => {
+            assert_eq!(
+                PathSegments::new($url).map(|m| m.to_string()).collect::<Vec<_>>(),
+                Vec::<String>::default()
+            );
+
+        };
+    }
+
+    #[test]
+    fn test_path_segments() {
+        segments!("/");
+        segments!("");
+        segments!("/path" => "path");
+        segments!("path" => "path");
+        segments!("path/" => "path");
+        segments!("/path/" => "path");
+        segments!("/path/subpath" => "path", "subpath");
+        segments!("/path/subpath/" => "path", "subpath");
+        segments!("path/subpath/" => "path", "subpath");
+        segments!("/a//b" => "a", "", "b");
+        segments!("https://test.com/test/path/subpath/" => "https:", "", "test.com", "test", "path", "subpath");
+    }
+
+    #[test]
+    fn test_normalize() {
+        let rfc = Normalization {
+            remove_dot_segments: true,
+            ..Default::default()
+        };
+        assert_eq!(rfc.apply("/a/b/c/./../../g").unwrap(), "/a/g");
+        assert_eq!(rfc.apply("/a/b/..").unwrap(), "/a/");
+        assert_eq!(rfc.apply("/a/%2E%2e/b").unwrap(), "/b");
+        assert_eq!(rfc.apply("/../a").unwrap(), "/a");
+        assert_eq!(rfc.apply("/..").unwrap(), "/");
+        assert_eq!(rfc.apply("/a/...").unwrap(), "/a/...");
+        assert_eq!(rfc.apply("/a€").unwrap(), "/a€");
+        assert_eq!(rfc.apply("/€/./ü/..").unwrap(), "/€/");
+        assert_eq!(rfc.apply("/.€").unwrap(), "/.€");
+        assert!(matches!(rfc.apply("/a/b"), Ok(Cow::Borrowed(_))));
+
+        let collapse = Normalization {
+            empty_segments: EmptySegments::Collapse,
+            ..Default::default()
+        };
+        assert_eq!(collapse.apply("//a//b//").unwrap(), "/a/b/");
+        assert_eq!(collapse.apply("/a/../b").unwrap(), "/a/../b");
+
+        let strict = Normalization {
+            remove_dot_segments: true,
+            empty_segments: EmptySegments::Reject,
+            reject_traversal: true,
+        };
+        assert_eq!(strict.apply("/a//b"), Err(NormalizeError::EmptySegment));
+        assert_eq!(strict.apply("/a/../../etc"), Err(NormalizeError::Traversal));
+        assert_eq!(strict.apply("/a/b/../c/").unwrap(), "/a/c/");
+
+        let traversal = Normalization {
+            reject_traversal: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            traversal.apply("/%2e%2e/etc"),
+            Err(NormalizeError::Traversal)
+        );
+        assert_eq!(traversal.apply("/a/../b").unwrap(), "/a/../b");
+    }
+}
@@ -1,9 +1,20 @@
-use super::{AsSegments, Params, Segment, Segments};
+use super::{
+    AsSegments, Constraint, Match, Params, PathParams, Redirect, Resolved, Segment, Segments,
+};
 use crate::arena::{Arena, Id};
-use crate::matcher::into_segments;
+use crate::constraint::CompiledConstraint;
+use crate::matcher::match_composite_decoded;
+use crate::params::{CaptureSink, CollectAll, Only, OwnedValues, Ranked, TrySink};
+use crate::path::{has_trailing_slash, PathSegments};
+use crate::percent::{decode, decode_param};
+use crate::{CaseSensitivity, MatchOptions, TrailingSlash, TryParams};
+use core::{cmp::Reverse, ops::Range};
+
 use alloc::{
-    collections::btree_map::BTreeMap,
+    borrow::Cow,
+    collections::{btree_map::BTreeMap, BTreeSet},
     string::{String, ToString},
+    vec,
     vec::Vec,
 };
 
@@ -12,6 +23,9 @@ use alloc::{
 pub struct Route<'a, H> {
     pub segments: Segments<'a>,
     pub handlers: Option<H>,
+    /// See [`PathRouter::register_with_priority`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub priority: i32,
 }
 
 impl<'a, H> Route<'a, H> {
@@ -19,6 +33,7 @@ impl<'a, H> Route<'a, H> {
         Route {
             segments: self.segments.to_owned(),
             handlers: self.handlers,
+            priority: self.priority,
         }
     }
 
@@ -33,6 +48,7 @@ impl<'a, H> Route<'a, H> {
         Route {
             segments: self.segments,
             handlers: self.handlers.map(func),
+            priority: self.priority,
         }
     }
 }
@@ -41,52 +57,264 @@ impl<'a, H> Route<'a, H> {
 #[derive(Debug, Clone)]
 struct Named<H> {
     name: String,
+    constraint: Option<CompiledConstraint>,
     handle: H,
 }
 
+impl<H> Named<H> {
+    fn accepts(&self, value: &str) -> bool {
+        self.constraint
+            .as_ref()
+            .map(|m| m.is_match(value))
+            .unwrap_or(true)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+struct Composite {
+    parts: Vec<Segment<'static>>,
+    constraints: Vec<Option<CompiledConstraint>>,
+    case: CaseSensitivity,
+    handle: Id,
+}
+
+impl Composite {
+    fn new(parts: &[Segment<'static>], case: CaseSensitivity, handle: Id) -> Composite {
+        let constraints = parts
+            .iter()
+            .map(|m| match m {
+                Segment::Parameter(_, constraint) => {
+                    constraint.clone().map(CompiledConstraint::new)
+                }
+                _ => None,
+            })
+            .collect();
+
+        Composite {
+            parts: parts.to_vec(),
+            constraints,
+            case,
+            handle,
+        }
+    }
+
+    fn is_match<'s, 'p, C: CaptureSink<'s, 'p>>(
+        &'s self,
+        value: Cow<'p, str>,
+        captures: &mut C,
+    ) -> bool {
+        let accepts = |idx: usize, value: &str| {
+            self.constraints[idx]
+                .as_ref()
+                .map(|m| m.is_match(value))
+                .unwrap_or(true)
+        };
+        match_composite_decoded(&self.parts, value, self.case, accepts, captures)
+    }
+}
+
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
-struct Node<H> {
+struct Literal {
+    // Folded under `case`
+    text: String,
+    case: CaseSensitivity,
+    // Named alternations capture the component
+    name: Option<String>,
+    handle: Id,
+}
+
+impl Literal {
+    fn accepts(&self, value: &str) -> bool {
+        self.case.eq(&self.text, value)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default)]
+struct Node {
     constants: BTreeMap<String, Id>,
-    handle: Option<H>,
-    catchall: Option<Named<Id>>,
-    wildcard: Option<Named<Id>>,
-    segments: Option<Segments<'static>>,
+    // Case-insensitive constants and named alternations, keyed by their Unicode folding.
+    // Each edge compares the component under its own sensitivity
+    literals: BTreeMap<String, Vec<Literal>>,
+    // Parameters and stars get an edge per name, so captures are named like the matched route.
+    // All stars are tried at each length before growing any of them
+    catchalls: Vec<Named<Id>>,
+    // Constrained wildcards come first and are tried in registration order
+    wildcards: Vec<Named<Id>>,
+    // Components mixing literals and parameters, tried before the wildcards
+    composites: Vec<Composite>,
+    // Several nodes point to the same route when the template has optional segments
+    route: Option<Id>,
+    // The route was only added for the other form of the trailing slash
+    #[cfg_attr(feature = "serde", serde(default))]
+    implicit: bool,
 }
 
-impl<H> Default for Node<H> {
-    fn default() -> Node<H> {
-        Node {
-            constants: Default::default(),
-            handle: Default::default(),
-            catchall: None,
-            wildcard: None,
-            segments: None,
+impl Node {
+    fn literal(&self, text: &str, name: Option<&str>, case: CaseSensitivity) -> Option<Id> {
+        if case.is_sensitive() && name.is_none() {
+            return self.constants.get(text).copied();
+        }
+
+        let text = case.fold(text);
+        self.literals
+            .get(CaseSensitivity::Insensitive.fold(&text).as_ref())?
+            .iter()
+            .find(|m| m.case == case && m.name.as_deref() == name && m.text == text)
+            .map(|m| m.handle)
+    }
+
+    fn add_literal(&mut self, text: &str, name: Option<&str>, case: CaseSensitivity, handle: Id) {
+        if case.is_sensitive() && name.is_none() {
+            self.constants.insert(text.to_string(), handle);
+            return;
         }
+
+        let text = case.fold(text).into_owned();
+        self.literals
+            .entry(CaseSensitivity::Insensitive.fold(&text).into_owned())
+            .or_default()
+            .push(Literal {
+                text,
+                case,
+                name: name.map(Into::into),
+                handle,
+            });
     }
+
+    // The edge of the empty constant standing for a trailing slash
+    fn slash(&self) -> Option<Id> {
+        self.constants.get("").copied().or_else(|| {
+            self.literals
+                .get("")
+                .and_then(|m| m.iter().find(|m| m.name.is_none()))
+                .map(|m| m.handle)
+        })
+    }
+
+    fn wildcard(&self, name: &str, constraint: Option<&Constraint<'_>>) -> Option<&Named<Id>> {
+        self.wildcards
+            .iter()
+            .find(|m| m.name == name && m.constraint.as_ref().map(|m| m.constraint()) == constraint)
+    }
+
+    fn catchall(&self, name: &str) -> Option<&Named<Id>> {
+        self.catchalls.iter().find(|m| m.name == name)
+    }
+
+    // The nodes the edges lead to, in order of precedence
+    fn edges(&self) -> impl Iterator<Item = Id> + '_ {
+        self.constants
+            .values()
+            .copied()
+            .chain(self.literals.values().flatten().map(|m| m.handle))
+            .chain(self.composites.iter().map(|m| m.handle))
+            .chain(self.wildcards.iter().map(|m| m.handle))
+            .chain(self.catchalls.iter().map(|m| m.handle))
+    }
+}
+
+// An edge a component can take out of a node
+#[derive(Clone, Copy)]
+enum Edge<'s> {
+    // A constant or alternation, capturing the component when it's named
+    Literal(Option<&'s str>, Id),
+    Composite(&'s Composite),
+    Wildcard(&'s Named<Id>),
+    Stars(&'s [Named<Id>]),
+}
+
+// The node after a star, the component it failed to match from, whether the path's
+// trailing slash was left and the sink's state. A star tries every split of the path,
+// without remembering failures several stars would try the same tails over and over
+type Failed = BTreeSet<(Id, usize, bool, usize)>;
+
+// How the end of the path is compared with a trailing slash on the template
+#[derive(Debug, Clone, Copy)]
+struct Trailing {
+    // The path ends in a slash which no star took
+    slash: bool,
+    strict: bool,
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub struct PathRouter<H> {
-    arena: Arena<Node<H>>,
+    arena: Arena<Node>,
+    routes: Arena<Route<'static, H>>,
     root: Id,
+    options: MatchOptions,
+    // Set once a route has a priority other than the default
+    #[cfg_attr(feature = "serde", serde(default))]
+    prioritized: bool,
+    // No route has a higher priority than this
+    #[cfg_attr(feature = "serde", serde(default))]
+    highest: i32,
+    // How many nodes point to each route
+    #[cfg_attr(feature = "serde", serde(default))]
+    references: BTreeMap<Id, usize>,
+}
+
+impl<H> Default for PathRouter<H> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<H> PathRouter<H> {
     pub fn new() -> PathRouter<H> {
+        PathRouter::with_options(MatchOptions::default())
+    }
+
+    pub fn with_options(options: MatchOptions) -> PathRouter<H> {
         let mut arena = Arena::default();
         let root = arena.alloc(Node::default());
-        PathRouter { arena, root }
+        PathRouter {
+            arena,
+            routes: Arena::default(),
+            root,
+            options,
+            prioritized: false,
+            highest: 0,
+            references: BTreeMap::new(),
+        }
+    }
+
+    pub fn options(&self) -> &MatchOptions {
+        &self.options
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (&Segments<'_>, &H)> {
-        self.arena
+        self.routes
             .iter()
-            .filter_map(|m| match (&m.segments, &m.handle) {
-                (Some(s), Some(h)) => Some((s, h)),
-                _ => None,
-            })
+            .filter_map(|m| m.handlers.as_ref().map(|h| (&m.segments, h)))
+    }
+
+    /// The routes in the order matching prefers them when several match a path,
+    /// by priority and then by the precedence of their segments
+    pub fn iter_by_precedence(&self) -> impl Iterator<Item = (&Segments<'_>, &H)> {
+        let mut order = Vec::new();
+        self.collect_routes(self.root, &mut BTreeSet::new(), &mut order);
+        order.sort_by_key(|m| Reverse(self.routes[*m].priority));
+        order.into_iter().filter_map(move |id| {
+            let route = &self.routes[id];
+            route.handlers.as_ref().map(|h| (&route.segments, h))
+        })
+    }
+
+    fn collect_routes(&self, node: Id, seen: &mut BTreeSet<Id>, order: &mut Vec<Id>) {
+        let node = &self.arena[node];
+        if let Some(route) = node.route {
+            if seen.insert(route) {
+                order.push(route);
+            }
+        }
+
+        for edge in node.edges() {
+            self.collect_routes(edge, seen, order);
+        }
     }
 
     pub fn register<'a, S: AsSegments<'a> + 'a>(
@@ -94,126 +322,390 @@ impl<H> PathRouter<H> {
         path: S,
         handle: H,
     ) -> Result<&mut Self, S::Error> {
-        let mut current = self.root;
+        self.register_with_case(path, handle, self.options.case_sensitivity)
+    }
 
-        let segments = path
-            .as_segments()?
-            .map(|m| m.to_owned())
-            .collect::<Vec<_>>();
+    /// Register a route whose constants are compared with the given sensitivity
+    /// instead of the one in the router options
+    pub fn register_with_case<'a, S: AsSegments<'a> + 'a>(
+        &mut self,
+        path: S,
+        handle: H,
+        case: CaseSensitivity,
+    ) -> Result<&mut Self, S::Error> {
+        self.register_inner(path, handle, case, 0)
+    }
 
-        'path: for segment in &segments {
-            //
-            match segment {
-                Segment::Constant(path) => {
-                    if let Some(node) = self.arena[current].constants.get(path.as_ref()) {
-                        current = *node;
-                        continue 'path;
-                    }
+    /// Register a route which is preferred over routes of a lower priority whenever both
+    /// match a path, overriding the precedence of the segments. Routes of the same priority
+    /// fall back to that precedence, the default priority is 0
+    pub fn register_with_priority<'a, S: AsSegments<'a> + 'a>(
+        &mut self,
+        path: S,
+        handle: H,
+        priority: i32,
+    ) -> Result<&mut Self, S::Error> {
+        self.register_inner(path, handle, self.options.case_sensitivity, priority)
+    }
 
-                    let node = self.arena.alloc(Node::default());
-                    self.arena[current].constants.insert(path.to_string(), node);
-                    current = node;
-                }
-                Segment::Parameter(param) => {
-                    if let Some(wildcard) = &self.arena[current].wildcard {
-                        // TODO: Check if names is the same
-                        current = wildcard.handle;
-                        continue 'path;
-                    } else {
-                        let node = self.arena.alloc(Node::default());
-                        self.arena[current].wildcard = Some(Named {
-                            name: param.to_string(),
-                            handle: node,
-                        });
-                        current = node;
-                        continue 'path;
-                    };
-                }
-                Segment::Star(star) => {
-                    if let Some(star) = &self.arena[current].catchall {
-                        current = star.handle;
-                    } else {
-                        let node = self.arena.alloc(Node::default());
-                        self.arena[current].catchall = Some(Named {
-                            name: star.to_string(),
-                            handle: node,
-                        });
-                        current = node;
-                        continue 'path;
+    fn register_inner<'a, S: AsSegments<'a> + 'a>(
+        &mut self,
+        path: S,
+        handle: H,
+        case: CaseSensitivity,
+        priority: i32,
+    ) -> Result<&mut Self, S::Error> {
+        let segments = Segments(
+            path.as_segments()?
+                .map(|m| m.to_owned())
+                .collect::<Vec<_>>(),
+        );
+
+        // Unless the trailing slash matters, the other form leads to the route as well
+        // as long as no route was registered for it
+        let slash = Segment::Constant(Cow::Borrowed(""));
+        let variants = segments.variants();
+        let toggled = if self.options.trailing_slash == TrailingSlash::Ignore {
+            variants
+                .iter()
+                .filter_map(|variant| match variant.split_last() {
+                    Some((Segment::Constant(c), rest)) if c.is_empty() => Some(rest.to_vec()),
+                    Some((Segment::Star(_), _)) | None => None,
+                    Some(_) => {
+                        let mut variant = variant.clone();
+                        variant.push(&slash);
+                        Some(variant)
                     }
-                }
-            };
+                })
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+
+        // A template only differing by the names of its parameters replaces the route too
+        let mut equivalent = Vec::new();
+        for variant in &variants {
+            self.equivalent(self.root, variant, case, &mut equivalent);
+        }
+        for variant in &toggled {
+            let mut ends = Vec::new();
+            self.equivalent(self.root, variant, case, &mut ends);
+            equivalent.extend(ends.into_iter().filter(|m| self.arena[*m].implicit));
+        }
+
+        let nodes = variants
+            .into_iter()
+            .flat_map(|variant| self.insert(self.root, &variant, case))
+            .collect::<Vec<_>>();
+        let implicit = toggled
+            .into_iter()
+            .flat_map(|variant| self.insert(self.root, &variant, case))
+            .collect::<Vec<_>>();
+
+        let route = self.routes.alloc(Route {
+            segments,
+            handlers: Some(handle),
+            priority,
+        });
+        self.references.insert(route, 0);
+
+        for node in equivalent {
+            self.set_route(node, None, false);
+        }
+        for node in nodes {
+            self.set_route(node, Some(route), false);
+        }
+        for node in implicit {
+            let current = &self.arena[node];
+            if current.route.is_none() || current.implicit {
+                self.set_route(node, Some(route), true);
+            }
         }
 
-        self.arena[current].segments = Some(Segments(segments));
-        self.arena[current].handle = Some(handle);
+        self.prioritized |= priority != 0;
+        self.highest = self.highest.max(priority);
 
         Ok(self)
     }
 
-    pub fn get_route<'a, S: AsSegments<'a>>(&self, path: S) -> Option<&H> {
-        let node = self.get_route_inner(path)?;
-        self.arena[node].handle.as_ref()
+    #[cfg(feature = "router")]
+    pub(crate) fn priority_with_case<'a, S: AsSegments<'a>>(
+        &self,
+        path: S,
+        case: CaseSensitivity,
+    ) -> Option<i32> {
+        let route = self.get_route_inner(path, case)?;
+        Some(self.routes[route].priority)
     }
 
-    pub fn get_route_mut<'a, S: AsSegments<'a>>(&mut self, path: S) -> Option<&mut H> {
-        let node = self.get_route_inner(path)?;
-        self.arena[node].handle.as_mut()
+    /// Change the priority of a registered route
+    #[cfg(feature = "router")]
+    pub(crate) fn set_priority_with_case<'a, S: AsSegments<'a>>(
+        &mut self,
+        path: S,
+        case: CaseSensitivity,
+        priority: i32,
+    ) -> bool {
+        let Some(route) = self.get_route_inner(path, case) else {
+            return false;
+        };
+
+        self.routes[route].priority = priority;
+        self.prioritized |= priority != 0;
+        self.highest = self.highest.max(priority);
+        true
     }
 
-    fn get_route_inner<'a, S: AsSegments<'a>>(&self, path: S) -> Option<Id> {
-        let mut current = self.root;
+    // Routes no node points to anymore are dropped
+    fn set_route(&mut self, node: Id, route: Option<Id>, implicit: bool) {
+        if let Some(old) = self.arena[node].route {
+            self.references(old);
+        }
+        if let Some(route) = route {
+            *self.references(route) += 1;
+        }
 
-        let segments = path
-            .as_segments()
-            .ok()?
-            .map(|m| m.to_owned())
-            .collect::<Vec<_>>();
+        self.arena[node].implicit = implicit;
+        let Some(old) = core::mem::replace(&mut self.arena[node].route, route) else {
+            return;
+        };
 
-        'path: for segment in &segments {
-            //
-            match segment {
-                Segment::Constant(path) => {
-                    if let Some(node) = self.arena[current].constants.get(path.as_ref()) {
-                        current = *node;
-                        continue 'path;
-                    }
+        let references = self.references(old);
+        *references -= 1;
+        if *references == 0 {
+            self.references.remove(&old);
+            self.routes[old].handlers = None;
+        }
+    }
 
-                    return None;
+    // Routers deserialized without the counts get them from the arena
+    fn references(&mut self, route: Id) -> &mut usize {
+        let arena = &self.arena;
+        self.references
+            .entry(route)
+            .or_insert_with(|| arena.iter().filter(|m| m.route == Some(route)).count())
+    }
+
+    // Collects the nodes the template ends at when the names of parameters and stars are
+    // ignored. Alternations are followed through their first alternative
+    fn equivalent(
+        &self,
+        current: Id,
+        segments: &[&Segment<'_>],
+        case: CaseSensitivity,
+        ends: &mut Vec<Id>,
+    ) {
+        let Some((segment, rest)) = segments.split_first() else {
+            ends.push(current);
+            return;
+        };
+
+        let node = &self.arena[current];
+        match segment {
+            Segment::Constant(path) => {
+                if let Some(next) = node.literal(path, None, case) {
+                    self.equivalent(next, rest, case, ends);
+                }
+            }
+            Segment::Parameter(_, constraint) => {
+                for wildcard in node.wildcards.iter().filter(|m| {
+                    m.constraint.as_ref().map(|m| m.constraint()) == constraint.as_ref()
+                }) {
+                    self.equivalent(wildcard.handle, rest, case, ends);
+                }
+            }
+            Segment::Composite(parts) => {
+                if let Some(composite) = node
+                    .composites
+                    .iter()
+                    .find(|m| &m.parts == parts && m.case == case)
+                {
+                    self.equivalent(composite.handle, rest, case, ends);
+                }
+            }
+            Segment::Star(_) => {
+                for star in &node.catchalls {
+                    self.equivalent(star.handle, rest, case, ends);
+                }
+            }
+            Segment::Alternation(name, alternatives) => {
+                let next = alternatives
+                    .first()
+                    .and_then(|m| node.literal(m, name.as_deref(), case));
+                if let Some(next) = next {
+                    self.equivalent(next, rest, case, ends);
+                }
+            }
+            Segment::Optional(_) => {}
+        }
+    }
+
+    // Returns the nodes the template ends at. That is a single node unless an alternation
+    // had to fan out into subtrees which already existed.
+    fn insert(
+        &mut self,
+        current: Id,
+        segments: &[&Segment<'static>],
+        case: CaseSensitivity,
+    ) -> Vec<Id> {
+        let Some((segment, rest)) = segments.split_first() else {
+            return vec![current];
+        };
+
+        let next = match segment {
+            Segment::Constant(path) => {
+                if let Some(node) = self.arena[current].literal(path, None, case) {
+                    node
+                } else {
+                    let node = self.arena.alloc(Node::default());
+                    self.arena[current].add_literal(path, None, case, node);
+                    node
                 }
-                Segment::Parameter(_) => {
-                    //
-                    if let Some(wildcard) = &self.arena[current].wildcard {
-                        // TODO: Check if names is the same
-                        current = wildcard.handle;
-                        continue 'path;
+            }
+            Segment::Parameter(param, constraint) => {
+                if let Some(wildcard) = self.arena[current].wildcard(param, constraint.as_ref()) {
+                    wildcard.handle
+                } else {
+                    let node = self.arena.alloc(Node::default());
+                    let wildcards = &mut self.arena[current].wildcards;
+                    let idx = if constraint.is_some() {
+                        wildcards
+                            .iter()
+                            .position(|m| m.constraint.is_none())
+                            .unwrap_or(wildcards.len())
                     } else {
-                        return None;
+                        wildcards.len()
                     };
+                    wildcards.insert(
+                        idx,
+                        Named {
+                            name: param.to_string(),
+                            constraint: constraint.clone().map(CompiledConstraint::new),
+                            handle: node,
+                        },
+                    );
+                    node
                 }
-                Segment::Star(_) => {
-                    //
-                    if let Some(star) = &self.arena[current].catchall {
-                        current = star.handle;
-                    } else {
-                        return None;
-                    }
+            }
+            Segment::Composite(parts) => {
+                if let Some(composite) = self.arena[current]
+                    .composites
+                    .iter()
+                    .find(|m| &m.parts == parts && m.case == case)
+                {
+                    composite.handle
+                } else {
+                    let node = self.arena.alloc(Node::default());
+                    self.arena[current]
+                        .composites
+                        .push(Composite::new(parts, case, node));
+                    node
+                }
+            }
+            Segment::Star(star) => {
+                if let Some(star) = self.arena[current].catchall(star) {
+                    star.handle
+                } else {
+                    let node = self.arena.alloc(Node::default());
+                    self.arena[current].catchalls.push(Named {
+                        name: star.to_string(),
+                        constraint: None,
+                        handle: node,
+                    });
+                    node
+                }
+            }
+            Segment::Alternation(name, alternatives) => {
+                return self
+                    .insert_alternation(current, name.as_deref(), alternatives, case)
+                    .into_iter()
+                    .flat_map(|node| self.insert(node, rest, case))
+                    .collect();
+            }
+            Segment::Optional(_) => unreachable!("optional segments are expanded"),
+        };
+
+        self.insert(next, rest, case)
+    }
+
+    // Alternatives without an edge yet all point to one new node.
+    // Returns that node along with the nodes of the edges which already existed.
+    fn insert_alternation(
+        &mut self,
+        current: Id,
+        name: Option<&str>,
+        alternatives: &[Cow<'static, str>],
+        case: CaseSensitivity,
+    ) -> Vec<Id> {
+        let mut nodes = Vec::new();
+        let mut shared = None;
+
+        for alternative in alternatives {
+            let handle = match self.arena[current].literal(alternative, name, case) {
+                Some(handle) => handle,
+                None => {
+                    let handle = *shared.get_or_insert_with(|| self.arena.alloc(Node::default()));
+                    self.arena[current].add_literal(alternative, name, case, handle);
+                    handle
                 }
             };
+
+            if !nodes.contains(&handle) {
+                nodes.push(handle);
+            }
         }
 
-        Some(current)
+        nodes
+    }
+
+    pub fn get_route<'a, S: AsSegments<'a>>(&self, path: S) -> Option<&H> {
+        let route = self.get_route_inner(path, self.options.case_sensitivity)?;
+        self.routes[route].handlers.as_ref()
+    }
+
+    pub fn get_route_mut<'a, S: AsSegments<'a>>(&mut self, path: S) -> Option<&mut H> {
+        self.get_route_with_case_mut(path, self.options.case_sensitivity)
+    }
+
+    pub(crate) fn get_route_with_case_mut<'a, S: AsSegments<'a>>(
+        &mut self,
+        path: S,
+        case: CaseSensitivity,
+    ) -> Option<&mut H> {
+        let route = self.get_route_inner(path, case)?;
+        self.routes[route].handlers.as_mut()
+    }
+
+    // Parameters and stars are found whatever their names, like registering treats them
+    fn get_route_inner<'a, S: AsSegments<'a>>(&self, path: S, case: CaseSensitivity) -> Option<Id> {
+        let segments = Segments(
+            path.as_segments()
+                .ok()?
+                .map(|m| m.to_owned())
+                .collect::<Vec<_>>(),
+        );
+
+        // Optional segments are looked up through the variant with everything included
+        let variant = segments.variants().into_iter().next()?;
+        let mut ends = Vec::new();
+        self.equivalent(self.root, &variant, case, &mut ends);
+        ends.into_iter().find_map(|m| self.arena[m].route)
     }
 
     pub fn clear(&mut self) {
         self.arena = Arena::default();
+        self.routes = Arena::default();
         let root = self.arena.alloc(Node::default());
         self.root = root;
+        self.prioritized = false;
+        self.highest = 0;
+        self.references.clear();
     }
 
-    pub fn merge<'a>(&mut self, router: PathRouter<H>) {
-        for (path, handler) in router {
-            self.register(path, handler).expect("register");
+    pub fn merge(&mut self, router: PathRouter<H>) {
+        for (path, handler, priority) in router.into_prioritized() {
+            self.register_with_priority(path, handler, priority)
+                .expect("register");
         }
     }
 
@@ -222,128 +714,566 @@ impl<H> PathRouter<H> {
         path: S,
         router: PathRouter<H>,
     ) -> Result<(), S::Error> {
-        let mount = path.as_segments()?.collect::<Vec<_>>();
-        for (path, handler) in router {
+        let mut mount = path.as_segments()?.collect::<Vec<_>>();
+        // The mounted routes decide about the trailing slash
+        if matches!(mount.last(), Some(Segment::Constant(c)) if c.is_empty()) {
+            mount.pop();
+        }
+        for (path, handler, priority) in router.into_prioritized() {
             let mut mount = mount.clone();
             mount.extend(path);
-            self.register(mount, handler).expect("register");
+            self.register_with_priority(mount, handler, priority)
+                .expect("register");
         }
 
         Ok(())
     }
 
-    fn match_path_inner<'b, 'c, P: Params>(&self, path: &str, params: &'c mut P) -> Option<Id> {
-        let mut current_node = self.root;
-        let mut catch_all = self.arena[current_node].catchall.as_ref();
+    fn into_prioritized(self) -> impl Iterator<Item = (Segments<'static>, H, i32)> {
+        self.routes
+            .into_iter()
+            .filter_map(|m| Some((m.segments, m.handlers?, m.priority)))
+    }
+
+    // Returns the node the path ended at, the captures are added in path order.
+    // Unless `strict` is set the path may differ from the template by its trailing slash.
+    fn find_inner<'s, 'p, C: CaptureSink<'s, 'p>>(
+        &'s self,
+        path: &'p str,
+        strict: bool,
+        captures: &mut C,
+    ) -> Option<Id> {
+        // Values captured from a path which had to be rewritten are copied out of it
+        match self.options.normalization.apply(path).ok()? {
+            Cow::Borrowed(path) => self.find_normalized(path, strict, captures),
+            Cow::Owned(path) => self.find_normalized(&path, strict, &mut OwnedValues(captures)),
+        }
+    }
 
-        let segments = into_segments(path);
+    fn find_normalized<'s, 'p, C: CaptureSink<'s, 'p>>(
+        &'s self,
+        path: &'p str,
+        strict: bool,
+        captures: &mut C,
+    ) -> Option<Id> {
+        let trailing = Trailing {
+            slash: has_trailing_slash(path),
+            strict,
+        };
+        let iter = PathSegments::new(path).ranges();
+        if !self.prioritized {
+            return self.match_node(
+                self.root,
+                path,
+                iter,
+                trailing,
+                captures,
+                &mut Failed::new(),
+            );
+        }
 
-        let mut start = 0;
+        // The routes matching the path are ranked by their own priority, the first one found
+        // wins a tie. The captures are taken again once it's known which route they are for
+        let priority = |node: Id| {
+            self.arena[node]
+                .route
+                .map_or(i32::MIN, |m| self.routes[m].priority)
+        };
+        let mut ranked = Ranked::new(captures, self.highest, priority);
+        let found = self.match_node(
+            self.root,
+            path,
+            iter.clone(),
+            trailing,
+            &mut ranked,
+            &mut Failed::new(),
+        );
+        let (node, _) = match (found, ranked.best) {
+            (Some(found), _) => return Some(found),
+            (None, best) => best?,
+        };
 
-        for seg in segments {
-            start = seg.start;
-            if let Some(catch) = &self.arena[current_node].catchall {
-                catch_all = Some(catch);
-            }
+        self.match_node(
+            self.root,
+            path,
+            iter,
+            trailing,
+            &mut Only { captures, node },
+            &mut Failed::new(),
+        )
+    }
 
-            if let Some(constant) = self.arena[current_node].constants.get(&path[seg.clone()]) {
-                current_node = *constant;
-            } else if let Some(wildcard) = &self.arena[current_node].wildcard {
-                params.set((&wildcard.name).into(), path[seg].into());
-                current_node = wildcard.handle;
-            } else if let Some(catch) = catch_all {
-                let star = &path[seg.start..];
-                params.set((&catch.name).into(), star.into());
-                return Some(catch.handle);
-            } else {
-                return None;
-            }
-        }
+    fn match_path_inner<P: Params>(&self, path: &str, params: &mut P) -> Option<Id> {
+        let mut captures = Vec::new();
+        let node = self.find_inner(path, self.is_strict(), &mut captures)?;
 
-        if let Some(_) = self.arena[current_node].handle.as_ref() {
-            return Some(current_node);
-        } else if let Some(catch) = catch_all {
-            let star = &path[start..];
-            params.set((&catch.name).into(), star.into());
-            return Some(catch.handle);
-        } else {
-            return None;
+        for (name, value) in captures {
+            params.set(name.into(), value);
         }
+
+        self.arena[node].route
     }
 
-    pub fn match_path<'a, 'c, P: Params>(&'a self, path: &str, params: &'c mut P) -> Option<&'a H> {
-        let found = self.match_path_inner(path, params)?;
-        self.arena[found].handle.as_ref()
+    fn is_strict(&self) -> bool {
+        self.options.trailing_slash != TrailingSlash::Ignore
     }
 
-    pub fn match_path_mut<'a, 'c, P: Params>(
-        &'a mut self,
-        path: &str,
-        params: &'c mut P,
-    ) -> Option<&'a mut H> {
-        let found = self.match_path_inner(path, params)?;
-        self.arena[found].handle.as_mut()
+    // At the end of the path the route is taken from the node, or from its empty constant
+    // edge when it was registered with a trailing slash. The one written like the path comes first.
+    fn match_end<'s, 'p, C: CaptureSink<'s, 'p>>(
+        &'s self,
+        node: Id,
+        trailing: Trailing,
+        captures: &mut C,
+    ) -> Option<Id> {
+        let plain = Some(node).filter(|m| self.arena[*m].route.is_some());
+        let slashed = self.arena[node]
+            .slash()
+            .filter(|m| self.arena[*m].route.is_some());
+
+        let (exact, other) = if trailing.slash {
+            (slashed, plain)
+        } else {
+            (plain, slashed)
+        };
+
+        let other = other.filter(|_| !trailing.strict);
+        exact
+            .into_iter()
+            .chain(other)
+            .find(|node| self.finish(*node, captures))
     }
 
-    pub fn map<F, V>(self, mut mapper: F) -> PathRouter<V>
-    where
-        F: FnMut(H, Option<&Segments<'_>>) -> V,
-    {
-        PathRouter {
-            arena: self.arena.map(move |m| {
-                let segments = m.segments;
-                let handle = m.handle.map(|h| mapper(h, segments.as_ref()));
-                Node {
-                    constants: m.constants,
-                    handle,
-                    catchall: m.catchall,
-                    wildcard: m.wildcard,
-                    segments,
+    // Optional stars which were left out are reported as empty before the sink
+    // gets to accept the route
+    fn finish<'s, 'p, C: CaptureSink<'s, 'p>>(&'s self, node: Id, captures: &mut C) -> bool {
+        let Some(route) = self.arena[node].route else {
+            return false;
+        };
+
+        let mark = captures.mark();
+        for segment in self.routes[route].segments.iter() {
+            if let Segment::Optional(star) = segment {
+                if let Segment::Star(name) = &**star {
+                    if !captures.is_captured(name) && !captures.capture(name, "".into()) {
+                        captures.rollback(mark);
+                        return false;
+                    }
                 }
-            }),
-            root: self.root,
+            }
         }
-    }
-}
 
-impl<'a, H> IntoIterator for PathRouter<H> {
-    type IntoIter = IntoIter<H>;
-    type Item = (Segments<'static>, H);
-    fn into_iter(self) -> Self::IntoIter {
-        IntoIter(self.arena.into_iter())
+        if !captures.finish(node) {
+            captures.rollback(mark);
+            return false;
+        }
+
+        true
     }
-}
 
-pub struct IntoIter<H>(alloc::vec::IntoIter<Node<H>>);
+    // Edges are tried in order of precedence and the first one leading to a route wins:
+    // constants and alternations, composites, constrained parameters in registration order,
+    // plain parameters and finally the catch-all. Captures made on a branch which fails further
+    // down are dropped before the next edge is tried.
+    fn match_node<'s, 'p, C: CaptureSink<'s, 'p>>(
+        &'s self,
+        node: Id,
+        path: &'p str,
+        iter: impl Iterator<Item = Range<usize>> + Clone,
+        trailing: Trailing,
+        captures: &mut C,
+        failed: &mut Failed,
+    ) -> Option<Id> {
+        let current = &self.arena[node];
 
-impl<H> Iterator for IntoIter<H> {
-    type Item = (Segments<'static>, H);
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            let Some(next) = self.0.next() else {
-                return None;
+        let mut rest = iter.clone();
+        let Some(seg) = rest.next() else {
+            return self.match_end(node, trailing, captures);
+        };
+
+        let raw = &path[seg];
+        let mark = captures.mark();
+
+        // Empty components, as in `/a//b`, are only ever taken by a star
+        if raw.is_empty() {
+            return self.match_star(&current.catchalls, path, iter, trailing, captures, failed);
+        }
+
+        // Constants are compared fully decoded, parameters follow the options
+        let constant = decode(raw, false);
+
+        // Folding is skipped for nodes with case-sensitive constants only
+        let literals = if current.literals.is_empty() {
+            None
+        } else {
+            current
+                .literals
+                .get(CaseSensitivity::Insensitive.fold(&constant).as_ref())
+        };
+
+        let value = decode_param(raw, &self.options);
+
+        // The exact constant edge comes before literals accepting the same text
+        let edges = current
+            .constants
+            .get(constant.as_ref())
+            .map(|m| Edge::Literal(None, *m))
+            .into_iter()
+            .chain(
+                literals
+                    .into_iter()
+                    .flatten()
+                    .filter(|m| m.accepts(&constant))
+                    .map(|m| Edge::Literal(m.name.as_deref(), m.handle)),
+            )
+            .chain(
+                value
+                    .iter()
+                    .flat_map(|_| current.composites.iter().map(Edge::Composite)),
+            )
+            .chain(value.iter().flat_map(|value| {
+                current
+                    .wildcards
+                    .iter()
+                    .filter(move |m| m.accepts(value))
+                    .map(Edge::Wildcard)
+            }))
+            .chain(
+                Some(current.catchalls.as_slice())
+                    .filter(|m| !m.is_empty())
+                    .map(Edge::Stars),
+            );
+
+        let take = |edge: Edge<'s>, captures: &mut C, failed: &mut Failed| {
+            let found = match edge {
+                Edge::Literal(name, node) => {
+                    let captured = name.is_none_or(|name| captures.capture(name, constant.clone()));
+                    if captured {
+                        self.match_node(node, path, rest.clone(), trailing, captures, failed)
+                    } else {
+                        None
+                    }
+                }
+                Edge::Composite(composite) => {
+                    let value = value.clone()?;
+                    if composite.is_match(value, captures) {
+                        self.match_node(
+                            composite.handle,
+                            path,
+                            rest.clone(),
+                            trailing,
+                            captures,
+                            failed,
+                        )
+                    } else {
+                        None
+                    }
+                }
+                Edge::Wildcard(wildcard) => {
+                    let value = value.clone()?;
+                    if captures.capture(&wildcard.name, value) {
+                        self.match_node(
+                            wildcard.handle,
+                            path,
+                            rest.clone(),
+                            trailing,
+                            captures,
+                            failed,
+                        )
+                    } else {
+                        None
+                    }
+                }
+                Edge::Stars(stars) => {
+                    return self.match_star(stars, path, iter.clone(), trailing, captures, failed);
+                }
             };
 
-            match (next.segments, next.handle) {
-                (Some(segments), Some(handle)) => return Some((segments, handle)),
-                _ => {}
+            if found.is_none() {
+                captures.rollback(mark);
             }
-        }
+            found
+        };
+
+        edges
+            .into_iter()
+            .find_map(|edge| take(edge, captures, failed))
     }
-}
 
-#[cfg(test)]
-mod test {
-    pub use super::*;
-    use alloc::collections::BTreeMap;
+    // A star consumes at least one component. It grows one component at a time
+    // until the rest of the template matches, so a star followed by more segments
+    // takes the shortest run possible. Stars of different names are all tried at a
+    // length before it grows. A star taking the last component takes the trailing
+    // slash along with it.
+    fn match_star<'s, 'p, C: CaptureSink<'s, 'p>>(
+        &'s self,
+        stars: &'s [Named<Id>],
+        path: &'p str,
+        mut iter: impl Iterator<Item = Range<usize>> + Clone,
+        mut trailing: Trailing,
+        captures: &mut C,
+        failed: &mut Failed,
+    ) -> Option<Id> {
+        let start = iter.clone().next()?.start;
 
-    #[test]
-    fn test() {
-        let mut router = PathRouter::new();
+        while let Some(seg) = iter.next() {
+            let value = if iter.clone().next().is_none() {
+                trailing.slash = false;
+                &path[start..]
+            } else {
+                &path[start..seg.end]
+            };
 
-        router.register(&[], "root").unwrap();
+            let Some(value) = decode_param(value, &self.options) else {
+                continue;
+            };
 
-        assert_eq!(
+            let position = iter.clone().next().map_or(path.len(), |m| m.start);
+            for star in stars {
+                let key = (star.handle, position, trailing.slash, captures.state());
+                if failed.contains(&key) {
+                    continue;
+                }
+
+                let mark = captures.mark();
+                if captures.capture(&star.name, value.clone()) {
+                    if let Some(found) =
+                        self.match_node(star.handle, path, iter.clone(), trailing, captures, failed)
+                    {
+                        return Some(found);
+                    }
+                    failed.insert(key);
+                }
+                captures.rollback(mark);
+            }
+        }
+
+        None
+    }
+
+    /// Find the route for a path along with the template and captures
+    pub fn find<'p>(&self, path: &'p str) -> Option<Match<'_, 'p, H>> {
+        self.find_with(path, self.is_strict())
+    }
+
+    fn find_with<'p>(&self, path: &'p str, strict: bool) -> Option<Match<'_, 'p, H>> {
+        let mut params = Vec::new();
+        let node = self.find_inner(path, strict, &mut params)?;
+        let route = &self.routes[self.arena[node].route?];
+
+        Some(Match {
+            handler: route.handlers.as_ref()?,
+            template: &route.segments,
+            params,
+            node,
+            remainder: &path[path.len()..],
+        })
+    }
+
+    /// Like [`find`](PathRouter::find), but with [`TrailingSlash::RedirectToCanonical`]
+    /// a path matching a route only once its trailing slash is added or removed
+    /// resolves to a redirect there
+    pub fn resolve<'p>(&self, path: &'p str) -> Option<Resolved<'_, 'p, H>> {
+        if let Some(found) = self.find(path) {
+            return Some(Resolved::Found(found));
+        }
+
+        if self.options.trailing_slash != TrailingSlash::RedirectToCanonical {
+            return None;
+        }
+
+        self.find_with(path, false)?;
+        let path = self.options.normalization.apply(path).ok()?;
+        Some(Resolved::Redirect(Redirect::toggle_slash(&path)))
+    }
+
+    /// The route registered for the longest leading part of the path, cut at a slash, with the
+    /// rest of the path as [`Match::remainder`]. `/admin/users/1` finds `/admin` with `/users/1`
+    /// left over when nothing deeper is registered. The remainder is a slice of the path as given
+    pub fn match_prefix<'p>(&self, path: &'p str) -> Option<Match<'_, 'p, H>> {
+        self.find_prefix(path, Some)
+    }
+
+    // Tries the leading parts of the path, longest first, until `func` takes a match
+    pub(crate) fn find_prefix<'a, 'p, U>(
+        &'a self,
+        path: &'p str,
+        mut func: impl FnMut(Match<'a, 'p, H>) -> Option<U>,
+    ) -> Option<U> {
+        let mut ends = vec![path.len()];
+        ends.extend(
+            PathSegments::new(path)
+                .ranges()
+                .map(|m| m.end)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev(),
+        );
+        ends.push(0);
+        ends.dedup();
+
+        let strict = self.is_strict();
+        ends.into_iter().find_map(|end| {
+            let mut found = self.find_with(&path[..end], strict)?;
+            found.remainder = &path[end..];
+            func(found)
+        })
+    }
+
+    /// Every route matching the path, each with its own captures, by priority and then from
+    /// most to least specific. That is the order [`match_path`](PathRouter::match_path) ranks
+    /// them in, so the first one is the route it picks
+    pub fn match_all<'p>(&self, path: &'p str) -> Vec<Match<'_, 'p, H>> {
+        let mut collect = CollectAll::default();
+        self.find_inner(path, self.is_strict(), &mut collect);
+
+        let mut seen: Vec<Id> = Vec::new();
+        let mut found = collect
+            .found
+            .into_iter()
+            .filter_map(|(node, params)| {
+                // A route can be reached again with the components split another way
+                let id = self.arena[node].route?;
+                if seen.contains(&id) {
+                    return None;
+                }
+                seen.push(id);
+
+                let route = &self.routes[id];
+                Some(Match {
+                    handler: route.handlers.as_ref()?,
+                    template: &route.segments,
+                    params,
+                    node,
+                    remainder: &path[path.len()..],
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if self.prioritized {
+            found.sort_by_key(|m| {
+                Reverse(
+                    self.arena[m.node]
+                        .route
+                        .map_or(i32::MIN, |m| self.routes[m].priority),
+                )
+            });
+        }
+        found
+    }
+
+    /// Like [`match_path`](PathRouter::match_path), capturing into [`PathParams`] without copying.
+    /// `params` is left as it was when no route matches
+    pub fn match_path_params<'r, 'p, const N: usize>(
+        &'r self,
+        path: &'p str,
+        params: &mut PathParams<'r, 'p, N>,
+    ) -> Option<&'r H> {
+        let node = self.find_inner(path, self.is_strict(), params)?;
+        self.routes[self.arena[node].route?].handlers.as_ref()
+    }
+
+    /// The most parameters any registered route captures
+    pub fn max_params(&self) -> usize {
+        self.routes
+            .iter()
+            .filter(|m| m.handlers.is_some())
+            .map(|m| m.segments.param_count())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Find the route for a path. When several routes could match, a component prefers
+    /// constants, then composites, then constrained parameters, then plain parameters
+    /// and the catch-all last, backtracking whenever the rest of the path doesn't match.
+    /// Only the parameters of the route found end up in `params`.
+    pub fn match_path<'a, P: Params>(&'a self, path: &str, params: &mut P) -> Option<&'a H> {
+        let found = self.match_path_inner(path, params)?;
+        self.routes[found].handlers.as_ref()
+    }
+
+    /// Like [`match_path`](PathRouter::match_path), writing into `params` while matching.
+    /// A value `params` refuses fails that branch and matching backtracks to the next
+    /// candidate, `params` is rolled back to where it started when nothing matches
+    pub fn try_match_path<'a, P: TryParams + ?Sized>(
+        &'a self,
+        path: &str,
+        params: &mut P,
+    ) -> Option<&'a H> {
+        let mut sink = TrySink::new(params);
+        let node = self.find_inner(path, self.is_strict(), &mut sink)?;
+        self.routes[self.arena[node].route?].handlers.as_ref()
+    }
+
+    pub fn match_path_mut<'a, P: Params>(
+        &'a mut self,
+        path: &str,
+        params: &mut P,
+    ) -> Option<&'a mut H> {
+        let found = self.match_path_inner(path, params)?;
+        self.routes[found].handlers.as_mut()
+    }
+
+    pub fn map<F, V>(self, mut mapper: F) -> PathRouter<V>
+    where
+        F: FnMut(H, Option<&Segments<'_>>) -> V,
+    {
+        PathRouter {
+            arena: self.arena,
+            routes: self.routes.map(move |m| {
+                let segments = m.segments;
+                let handlers = m.handlers.map(|h| mapper(h, Some(&segments)));
+                Route {
+                    segments,
+                    handlers,
+                    priority: m.priority,
+                }
+            }),
+            root: self.root,
+            options: self.options,
+            prioritized: self.prioritized,
+            highest: self.highest,
+            references: self.references,
+        }
+    }
+}
+
+impl<H> IntoIterator for PathRouter<H> {
+    type IntoIter = IntoIter<H>;
+    type Item = (Segments<'static>, H);
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self.routes.into_iter())
+    }
+}
+
+pub struct IntoIter<H>(alloc::vec::IntoIter<Route<'static, H>>);
+
+impl<H> Iterator for IntoIter<H> {
+    type Item = (Segments<'static>, H);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let next = self.0.next()?;
+
+            if let Some(handle) = next.handlers {
+                return Some((next.segments, handle));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    pub use super::*;
+    use crate::{EmptySegments, Normalization};
+    use alloc::{collections::BTreeMap, vec};
+
+    #[test]
+    fn test() {
+        let mut router = PathRouter::new();
+
+        router.register(&[], "root").unwrap();
+
+        assert_eq!(
             router.match_path("", &mut BTreeMap::default()),
             Some(&"root")
         );
@@ -363,7 +1293,7 @@ mod test {
             .register(
                 &[
                     Segment::Constant("path".into()),
-                    Segment::Parameter("id".into()),
+                    Segment::Parameter("id".into(), None),
                 ],
                 "/path/:id",
             )
@@ -395,6 +1325,265 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_constraints() {
+        let mut router = PathRouter::new();
+
+        router
+            .register("/users/new", "new")
+            .unwrap()
+            .register("/users/:id<u64>", "user")
+            .unwrap()
+            .register("/v/:n<int(1..=9)>", "version")
+            .unwrap()
+            .register("/v/:name", "named")
+            .unwrap();
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/users/new", &mut params), Some(&"new"));
+        assert_eq!(router.match_path("/users/42", &mut params), Some(&"user"));
+        assert_eq!(params.get("id"), Some(&"42".into()));
+        assert_eq!(router.match_path("/users/abc", &mut params), None);
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/v/3", &mut params), Some(&"version"));
+        assert_eq!(params.get("n"), Some(&"3".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/v/10", &mut params), Some(&"named"));
+        assert_eq!(params.get("name"), Some(&"10".into()));
+
+        assert_eq!(router.get_route("/v/:n<int(1..=9)>"), Some(&"version"));
+        assert_eq!(router.get_route("/users/:id"), None);
+    }
+
+    #[test]
+    fn test_optional() {
+        let mut router = PathRouter::new();
+
+        router
+            .register("/:lang?/docs", "docs")
+            .unwrap()
+            .register("/files/*rest?", "files")
+            .unwrap();
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/docs", &mut params), Some(&"docs"));
+        assert_eq!(params.get("lang"), None);
+        assert_eq!(router.match_path("/da/docs", &mut params), Some(&"docs"));
+        assert_eq!(params.get("lang"), Some(&"da".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/files", &mut params), Some(&"files"));
+        assert_eq!(params.get("rest"), Some(&"".into()));
+        assert_eq!(
+            router.match_path("/files/img/logo.png", &mut params),
+            Some(&"files")
+        );
+        assert_eq!(params.get("rest"), Some(&"img/logo.png".into()));
+
+        assert_eq!(router.iter().count(), 2);
+        assert_eq!(router.get_route("/:lang?/docs"), Some(&"docs"));
+    }
+
+    #[test]
+    fn test_reregister() {
+        let mut router = PathRouter::new();
+
+        router
+            .register("/:lang?/docs", "docs")
+            .unwrap()
+            .register("/docs", "plain")
+            .unwrap();
+
+        assert_eq!(
+            router.match_path("/docs", &mut BTreeMap::default()),
+            Some(&"plain")
+        );
+        assert_eq!(
+            router.match_path("/en/docs", &mut BTreeMap::default()),
+            Some(&"docs")
+        );
+        assert_eq!(router.iter().count(), 2);
+
+        router.register("/:lang/docs", "replaced").unwrap();
+        assert_eq!(router.iter().count(), 2);
+        assert_eq!(
+            router.iter().map(|m| *m.1).collect::<Vec<_>>(),
+            vec!["plain", "replaced"]
+        );
+    }
+
+    #[test]
+    fn test_composite() {
+        let mut router = PathRouter::new();
+
+        router
+            .register("/files/:name.:ext", "file")
+            .unwrap()
+            .register("/files/:name", "any")
+            .unwrap()
+            .register("/v:version/api", "api")
+            .unwrap()
+            .register("/@:user", "user")
+            .unwrap();
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/files/logo.png", &mut params),
+            Some(&"file")
+        );
+        assert_eq!(params.get("name"), Some(&"logo".into()));
+        assert_eq!(params.get("ext"), Some(&"png".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/files/logo", &mut params), Some(&"any"));
+        assert_eq!(params.get("name"), Some(&"logo".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/v1/api", &mut params), Some(&"api"));
+        assert_eq!(params.get("version"), Some(&"1".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/@kildevaeld", &mut params),
+            Some(&"user")
+        );
+        assert_eq!(params.get("user"), Some(&"kildevaeld".into()));
+
+        assert_eq!(router.get_route("/v:version/api"), Some(&"api"));
+    }
+
+    #[test]
+    fn test_inner_star() {
+        let mut router = PathRouter::new();
+
+        router
+            .register("/repos/*path/blob/:rev", "blob")
+            .unwrap()
+            .register("/repos/*path/tree", "tree")
+            .unwrap()
+            .register("/buckets/:b/*key/acl", "acl")
+            .unwrap()
+            .register("/buckets/:b/*key", "object")
+            .unwrap();
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/repos/rust-lang/rust/blob/master", &mut params),
+            Some(&"blob")
+        );
+        assert_eq!(params.get("path"), Some(&"rust-lang/rust".into()));
+        assert_eq!(params.get("rev"), Some(&"master".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/repos/rust-lang/rust/tree", &mut params),
+            Some(&"tree")
+        );
+        assert_eq!(params.get("path"), Some(&"rust-lang/rust".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/buckets/media/img/logo.png/acl", &mut params),
+            Some(&"acl")
+        );
+        assert_eq!(params.get("b"), Some(&"media".into()));
+        assert_eq!(params.get("key"), Some(&"img/logo.png".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/buckets/media/img/logo.png", &mut params),
+            Some(&"object")
+        );
+        assert_eq!(params.get("key"), Some(&"img/logo.png".into()));
+
+        assert_eq!(
+            router.match_path("/repos/blob/master", &mut BTreeMap::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_percent_encoding() {
+        let mut router = PathRouter::new();
+
+        router
+            .register("/hello%20world", "hello")
+            .unwrap()
+            .register("/café/:name", "cafe")
+            .unwrap()
+            .register("/files/*path", "files")
+            .unwrap();
+
+        assert_eq!(
+            router.match_path("/hello%20world", &mut BTreeMap::default()),
+            Some(&"hello")
+        );
+        assert_eq!(
+            router.match_path("/hello world", &mut BTreeMap::default()),
+            Some(&"hello")
+        );
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/caf%C3%A9/J%C3%B8rgen%20Hansen", &mut params),
+            Some(&"cafe")
+        );
+        assert_eq!(params.get("name"), Some(&"Jørgen Hansen".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/café/a%2Fb", &mut params), Some(&"cafe"));
+        assert_eq!(params.get("name"), Some(&"a%2Fb".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/files/my%20dir/a.txt", &mut params),
+            Some(&"files")
+        );
+        assert_eq!(params.get("path"), Some(&"my dir/a.txt".into()));
+    }
+
+    #[test]
+    fn test_encoded_slash_policy() {
+        let routes = |options| {
+            let mut router = PathRouter::with_options(options);
+            router.register("/users/:name", "user").unwrap();
+            router
+        };
+
+        let router = routes(MatchOptions {
+            encoded_slash: crate::EncodedSlash::Reject,
+            ..Default::default()
+        });
+        assert_eq!(
+            router.match_path("/users/a%2Fb", &mut BTreeMap::default()),
+            None
+        );
+
+        let router = routes(MatchOptions {
+            encoded_slash: crate::EncodedSlash::Decode,
+            ..Default::default()
+        });
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/users/a%2Fb", &mut params),
+            Some(&"user")
+        );
+        assert_eq!(params.get("name"), Some(&"a/b".into()));
+
+        let router = routes(MatchOptions {
+            decode_params: false,
+            ..Default::default()
+        });
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/users/a%20b", &mut params),
+            Some(&"user")
+        );
+        assert_eq!(params.get("name"), Some(&"a%20b".into()));
+    }
+
     // #[test]
     // fn test_extend() {
     //     let mut router1 = Router::new();
@@ -452,4 +1641,621 @@ mod test {
     //         Some(&"statics2")
     //     );
     // }
+
+    #[test]
+    fn test_alternation() {
+        let mut router = PathRouter::new();
+
+        router
+            .register("/users/me", "me")
+            .unwrap()
+            .register("/:kind(users|people)/:id", "person")
+            .unwrap()
+            .register("/(about|info)", "about")
+            .unwrap();
+
+        assert_eq!(router.iter().count(), 3);
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/people/2", &mut params), Some(&"person"));
+        assert_eq!(params.get("kind"), Some(&"people".into()));
+        assert_eq!(params.get("id"), Some(&"2".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/users/1", &mut params), Some(&"person"));
+        assert_eq!(params.get("kind"), Some(&"users".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/users/me", &mut params), Some(&"me"));
+        assert_eq!(params.get("kind"), None);
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/info", &mut params), Some(&"about"));
+        assert_eq!(router.match_path("/about", &mut params), Some(&"about"));
+        assert!(params.is_empty());
+        assert_eq!(router.match_path("/others/1", &mut params), None);
+
+        assert_eq!(
+            router.get_route("/:kind(users|people)/:id"),
+            Some(&"person")
+        );
+        assert_eq!(router.get_route("/(about|info)"), Some(&"about"));
+
+        // `users` already has a subtree, so the template continues in both
+        router.register("/(users|staff)/:id/edit", "edit").unwrap();
+        assert_eq!(router.iter().count(), 4);
+        assert_eq!(
+            router.match_path("/users/1/edit", &mut params),
+            Some(&"edit")
+        );
+        assert_eq!(
+            router.match_path("/staff/1/edit", &mut params),
+            Some(&"edit")
+        );
+        assert_eq!(router.match_path("/users/me", &mut params), Some(&"me"));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let mut router = PathRouter::with_options(MatchOptions {
+            case_sensitivity: CaseSensitivity::Insensitive,
+            ..Default::default()
+        });
+
+        router
+            .register("/About", "about")
+            .unwrap()
+            .register("/Users/:Name", "user")
+            .unwrap()
+            .register("/files/:name.JSON", "json")
+            .unwrap()
+            .register("/:kind(Posts|Pages)", "kind")
+            .unwrap()
+            .register("/Été", "summer")
+            .unwrap();
+
+        let mut params = BTreeMap::default();
+        for path in ["/about", "/ABOUT", "/About"].iter() {
+            assert_eq!(router.match_path(path, &mut params), Some(&"about"));
+        }
+        assert_eq!(router.match_path("/ÉTÉ", &mut params), Some(&"summer"));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/USERS/MiXeD", &mut params),
+            Some(&"user")
+        );
+        assert_eq!(params.get("Name"), Some(&"MiXeD".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/files/Data.json", &mut params),
+            Some(&"json")
+        );
+        assert_eq!(params.get("name"), Some(&"Data".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/POSTS", &mut params), Some(&"kind"));
+        assert_eq!(params.get("kind"), Some(&"POSTS".into()));
+
+        assert_eq!(router.get_route("/about"), Some(&"about"));
+    }
+
+    #[test]
+    fn test_case_per_route() {
+        let mut router = PathRouter::new();
+
+        router
+            .register("/api/v1", "exact")
+            .unwrap()
+            .register_with_case("/Docs/Été", "docs", CaseSensitivity::AsciiInsensitive)
+            .unwrap();
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/api/v1", &mut params), Some(&"exact"));
+        assert_eq!(router.match_path("/API/v1", &mut params), None);
+        assert_eq!(router.match_path("/docs/Été", &mut params), Some(&"docs"));
+        assert_eq!(router.match_path("/DOCS/ÉTé", &mut params), Some(&"docs"));
+        assert_eq!(router.match_path("/docs/été", &mut params), None);
+    }
+
+    #[test]
+    fn test_backtracking() {
+        let mut router = PathRouter::new();
+
+        router
+            .register("/users/new/edit", "edit")
+            .unwrap()
+            .register("/users/:id/posts", "posts")
+            .unwrap()
+            .register("/users/:id<u64>/likes", "likes")
+            .unwrap()
+            .register("/users/*rest", "rest")
+            .unwrap()
+            .register("/:a<u64>/x", "x")
+            .unwrap()
+            .register("/:b/y", "y")
+            .unwrap();
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/users/new/posts", &mut params),
+            Some(&"posts")
+        );
+        assert_eq!(params.get("id"), Some(&"new".into()));
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/users/new/edit", &mut params),
+            Some(&"edit")
+        );
+        assert!(params.is_empty());
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/users/1/likes", &mut params),
+            Some(&"likes")
+        );
+        assert_eq!(
+            router.match_path("/users/1/posts", &mut params),
+            Some(&"posts")
+        );
+
+        let mut params = BTreeMap::default();
+        assert_eq!(
+            router.match_path("/users/new/likes", &mut params),
+            Some(&"rest")
+        );
+        assert_eq!(params.get("rest"), Some(&"new/likes".into()));
+        assert_eq!(params.get("id"), None);
+
+        let mut params = BTreeMap::default();
+        assert_eq!(router.match_path("/1/y", &mut params), Some(&"y"));
+        assert_eq!(params.get("a"), None);
+        assert_eq!(params.get("b"), Some(&"1".into()));
+    }
+
+    #[test]
+    fn test_find() {
+        let mut router = PathRouter::new();
+
+        router
+            .register("/repos/:owner/:repo/*path", "tree")
+            .unwrap()
+            .register("/about", "about")
+            .unwrap();
+
+        let found = router
+            .find("/repos/kildevaeld/router-rs/src/lib.rs")
+            .unwrap();
+        assert_eq!(found.handler, &"tree");
+        assert_eq!(found.template.to_string(), "/repos/:owner/:repo/*path");
+        assert_eq!(
+            found.params,
+            vec![
+                ("owner", "kildevaeld".into()),
+                ("repo", "router-rs".into()),
+                ("path", "src/lib.rs".into())
+            ]
+        );
+        assert_eq!(found.get("repo"), Some("router-rs"));
+        assert_eq!(found.remainder, "");
+
+        let mut params = BTreeMap::default();
+        found.params_into(&mut params);
+        assert_eq!(params.get("owner"), Some(&"kildevaeld".into()));
+
+        let owned = found.to_owned();
+        assert_eq!(owned.handler, "tree");
+        assert_eq!(owned.get("path"), Some("src/lib.rs"));
+        assert_eq!(owned.node, found.node);
+
+        let about = router.find("/about").unwrap();
+        assert!(about.params.is_empty());
+        assert_ne!(about.node, found.node);
+        assert!(router.find("/missing").is_none());
+    }
+
+    #[test]
+    fn test_match_path_params() {
+        let mut router = PathRouter::new();
+        router
+            .register("/repos/:owner/:repo/*path", "tree")
+            .unwrap()
+            .register("/users/:id", "user")
+            .unwrap()
+            .register("/about", "about")
+            .unwrap();
+
+        assert_eq!(router.max_params(), 3);
+
+        let mut params = PathParams::<'_, '_, 3>::new();
+        let found = router.match_path_params("/repos/kildevaeld/router-rs/src/lib.rs", &mut params);
+        assert_eq!(found, Some(&"tree"));
+        assert_eq!(params.get("repo"), Some("router-rs"));
+        assert_eq!(params.get(2), Some("src/lib.rs"));
+
+        params.clear();
+        assert_eq!(
+            router.match_path_params("/users/10/extra", &mut params),
+            None
+        );
+        assert!(params.is_empty());
+
+        assert_eq!(
+            router.match_path_params("/users/10", &mut params),
+            Some(&"user")
+        );
+        assert_eq!(params.iter().collect::<Vec<_>>(), [("id", "10")]);
+    }
+
+    /// Refuses non-numeric ids
+    #[derive(Default)]
+    struct NumericIds(Vec<(String, String)>);
+
+    impl TryParams for NumericIds {
+        fn try_set(&mut self, key: Cow<'_, str>, value: Cow<'_, str>) -> bool {
+            (key != "id" || value.parse::<u64>().is_ok()) && self.0.try_set(key, value)
+        }
+
+        fn checkpoint(&self) -> usize {
+            self.0.checkpoint()
+        }
+
+        fn rollback(&mut self, key: &str, checkpoint: usize) {
+            self.0.rollback(key, checkpoint)
+        }
+    }
+
+    #[test]
+    fn test_try_match_path() {
+        let mut router = PathRouter::new();
+        router
+            .register("/:org/users/:id/posts", "posts")
+            .unwrap()
+            .register("/:org/users/*rest", "rest")
+            .unwrap();
+
+        let mut params = NumericIds::default();
+        assert_eq!(
+            router.try_match_path("/acme/users/10/posts", &mut params),
+            Some(&"posts")
+        );
+        assert_eq!(params.0[1], ("id".to_string(), "10".to_string()));
+
+        let mut params = NumericIds::default();
+        assert_eq!(
+            router.try_match_path("/acme/users/bob/posts", &mut params),
+            Some(&"rest")
+        );
+        assert_eq!(
+            params.0,
+            vec![
+                ("org".to_string(), "acme".to_string()),
+                ("rest".to_string(), "bob/posts".to_string())
+            ]
+        );
+
+        let mut params = NumericIds::default();
+        assert_eq!(router.try_match_path("/acme/groups/bob", &mut params), None);
+        assert!(params.0.is_empty());
+
+        let mut params = BTreeMap::new();
+        assert_eq!(
+            router.try_match_path("/acme/users/bob/comments", &mut params),
+            Some(&"rest")
+        );
+        assert_eq!(params.get("id"), None);
+        assert_eq!(params.get("rest").map(|m| m.as_str()), Some("bob/comments"));
+    }
+
+    #[test]
+    fn test_trailing_slash() {
+        let build = |trailing_slash| {
+            let mut router = PathRouter::with_options(MatchOptions {
+                trailing_slash,
+                ..Default::default()
+            });
+            router
+                .register("/about", "about")
+                .unwrap()
+                .register("/docs/", "docs")
+                .unwrap()
+                .register("/users/:id", "user")
+                .unwrap()
+                .register("/users/:id/", "user/")
+                .unwrap()
+                .register("/files/*path", "files")
+                .unwrap();
+            router
+        };
+
+        let router = build(TrailingSlash::Ignore);
+        assert_eq!(router.match_path("/about/", &mut ()), Some(&"about"));
+        assert_eq!(router.match_path("/docs", &mut ()), Some(&"docs"));
+        // A form registered on its own keeps its route
+        assert_eq!(router.match_path("/users/1", &mut ()), Some(&"user"));
+        assert_eq!(router.match_path("/users/1/", &mut ()), Some(&"user/"));
+        assert_eq!(router.get_route("/users/:id"), Some(&"user"));
+        assert_eq!(router.get_route("/users/:id/"), Some(&"user/"));
+        assert_eq!(
+            router.iter().map(|(_, m)| *m).collect::<Vec<_>>(),
+            vec!["about", "docs", "user", "user/", "files"]
+        );
+        assert_eq!(router.get_route("/docs/"), Some(&"docs"));
+        assert_eq!(router.get_route("/docs"), Some(&"docs"));
+        assert_eq!(router.get_route("/about/"), Some(&"about"));
+        assert_eq!(router.find("/docs").unwrap().template.to_string(), "/docs/");
+
+        let mut router = build(TrailingSlash::Ignore);
+        router.register("/users/:id", "user2").unwrap();
+        router.register("/docs/", "docs2").unwrap();
+        assert_eq!(router.match_path("/users/1", &mut ()), Some(&"user2"));
+        assert_eq!(router.match_path("/users/1/", &mut ()), Some(&"user/"));
+        assert_eq!(router.match_path("/docs", &mut ()), Some(&"docs2"));
+        assert_eq!(
+            router.iter().map(|(_, m)| *m).collect::<Vec<_>>(),
+            vec!["about", "user/", "files", "user2", "docs2"]
+        );
+
+        let router = build(TrailingSlash::Strict);
+        assert_eq!(router.match_path("/about", &mut ()), Some(&"about"));
+        assert_eq!(router.match_path("/users/1", &mut ()), Some(&"user"));
+        assert_eq!(router.get_route("/docs"), None);
+        assert_eq!(router.match_path("/about/", &mut ()), None);
+        assert_eq!(router.match_path("/docs", &mut ()), None);
+        assert_eq!(router.match_path("/files/src/", &mut ()), Some(&"files"));
+        assert!(router.resolve("/about/").is_none());
+
+        let router = build(TrailingSlash::RedirectToCanonical);
+        assert_eq!(router.match_path("/about/", &mut ()), None);
+        assert!(matches!(
+            router.resolve("/about/"),
+            Some(Resolved::Redirect(Redirect { location })) if location == "/about"
+        ));
+        assert!(matches!(
+            router.resolve("/docs"),
+            Some(Resolved::Redirect(Redirect { location })) if location == "/docs/"
+        ));
+        assert!(matches!(
+            router.resolve("/users/1/"),
+            Some(Resolved::Found(found)) if found.handler == &"user/"
+        ));
+        assert!(router.resolve("/missing/").is_none());
+    }
+
+    #[test]
+    fn test_normalization() {
+        let mut router = PathRouter::with_options(MatchOptions {
+            normalization: Normalization {
+                remove_dot_segments: true,
+                empty_segments: EmptySegments::Reject,
+                reject_traversal: true,
+            },
+            ..Default::default()
+        });
+        router
+            .register("/public/*path", "public")
+            .unwrap()
+            .register("/admin", "admin")
+            .unwrap();
+
+        assert_eq!(
+            router.match_path("/public/../admin", &mut ()),
+            Some(&"admin")
+        );
+        assert_eq!(router.match_path("/public/../../admin", &mut ()), None);
+        assert_eq!(router.match_path("/public//a", &mut ()), None);
+
+        let found = router.find("/public/css/./site.css").unwrap();
+        assert_eq!(found.get("path"), Some("css/site.css"));
+
+        let mut params = PathParams::<'_, '_, 1>::new();
+        let handler = router.match_path_params("/public/a/b/../c", &mut params);
+        assert_eq!(handler, Some(&"public"));
+        assert_eq!(params.get("path"), Some("a/c"));
+
+        let mut plain = PathRouter::new();
+        plain.register("/a/:b/c", "abc").unwrap();
+        assert_eq!(plain.match_path("/a//c", &mut ()), None);
+    }
+
+    #[test]
+    fn test_match_all() {
+        let mut router = PathRouter::new();
+        router
+            .register("/users/*rest", "rest")
+            .unwrap()
+            .register("/users/:id/posts", "posts")
+            .unwrap()
+            .register("/users/me/posts", "me")
+            .unwrap()
+            .register("/users/:id<u64>/posts", "numeric")
+            .unwrap()
+            .register("/users/:id/:tab?", "tab")
+            .unwrap()
+            .register("/about", "about")
+            .unwrap();
+
+        let all = router.match_all("/users/me/posts");
+        assert_eq!(
+            all.iter().map(|m| *m.handler).collect::<Vec<_>>(),
+            ["me", "posts", "tab", "rest"]
+        );
+        assert_eq!(all[1].get("id"), Some("me"));
+        assert_eq!(all[2].get("tab"), Some("posts"));
+        assert_eq!(all[3].get("rest"), Some("me/posts"));
+        assert_eq!(
+            router.match_path("/users/me/posts", &mut ()),
+            Some(all[0].handler)
+        );
+
+        let all = router.match_all("/users/10/posts");
+        assert_eq!(
+            all.iter().map(|m| *m.handler).collect::<Vec<_>>(),
+            ["numeric", "posts", "tab", "rest"]
+        );
+
+        assert!(router.match_all("/missing").is_empty());
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut router = PathRouter::new();
+        router.register("/users/me", "me").unwrap();
+        router
+            .register_with_priority("/users/:id", "user", 1)
+            .unwrap();
+        router.register("/legacy/:id", "param").unwrap();
+        router
+            .register_with_priority("/legacy/*rest", "legacy", 2)
+            .unwrap();
+        router.register("/posts/new", "new").unwrap();
+        router.register("/posts/:id", "post").unwrap();
+
+        let mut params = BTreeMap::<String, String>::new();
+        assert_eq!(router.match_path("/users/me", &mut params), Some(&"user"));
+        assert_eq!(params.get("id").map(|m| m.as_str()), Some("me"));
+        assert_eq!(router.match_path("/legacy/5", &mut params), Some(&"legacy"));
+        assert_eq!(router.match_path("/posts/new", &mut params), Some(&"new"));
+
+        router
+            .register_with_priority("/:section/archive", "archive", 3)
+            .unwrap();
+        assert_eq!(
+            router.match_path("/posts/archive", &mut params),
+            Some(&"archive")
+        );
+        assert_eq!(router.match_path("/posts/1", &mut params), Some(&"post"));
+
+        let all = router
+            .match_all("/users/me")
+            .into_iter()
+            .map(|m| *m.handler)
+            .collect::<Vec<_>>();
+        assert_eq!(all, vec!["user", "me"]);
+
+        let order = router
+            .iter_by_precedence()
+            .map(|(_, handler)| *handler)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            vec!["archive", "legacy", "user", "param", "new", "post", "me"]
+        );
+
+        let mut merged = PathRouter::new();
+        merged.register("/users/me", "me").unwrap();
+        merged.merge(router);
+        assert_eq!(merged.match_path("/users/me", &mut params), Some(&"user"));
+
+        // Only the priority of the route that matches counts, not of routes further down
+        let mut router = PathRouter::new();
+        router.register("/u/new", "const").unwrap();
+        router.register("/u/:id", "param").unwrap();
+        router
+            .register_with_priority("/u/:id/admin", "admin", 10)
+            .unwrap();
+        assert_eq!(router.match_path("/u/new", &mut params), Some(&"const"));
+        assert_eq!(router.match_path("/u/1", &mut params), Some(&"param"));
+        assert_eq!(
+            router.match_path("/u/new/admin", &mut params),
+            Some(&"admin")
+        );
+        let order = router
+            .iter_by_precedence()
+            .map(|(_, handler)| *handler)
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!["admin", "const", "param"]);
+
+        router.clear();
+        router.register("/u/new", "const").unwrap();
+        router.register("/u/:id", "param").unwrap();
+        assert_eq!(router.match_path("/u/new", &mut params), Some(&"const"));
+    }
+
+    #[test]
+    fn test_param_names() {
+        let mut router = PathRouter::new();
+        router.register("/a/:x", "x").unwrap();
+        router.register("/a/:y/b", "y").unwrap();
+        router.register("/s/*x", "star x").unwrap();
+        router.register("/s/*y/end", "star y").unwrap();
+
+        let found = router.find("/a/1/b").unwrap();
+        assert_eq!(found.handler, &"y");
+        assert_eq!(found.template.to_string(), "/a/:y/b");
+        assert_eq!(found.get("y"), Some("1"));
+        assert_eq!(found.get("x"), None);
+        assert_eq!(router.find("/a/1").unwrap().get("x"), Some("1"));
+
+        let found = router.find("/s/1/2/end").unwrap();
+        assert_eq!(found.handler, &"star y");
+        assert_eq!(found.get("y"), Some("1/2"));
+        assert_eq!(router.find("/s/1/2").unwrap().get("x"), Some("1/2"));
+
+        // Templates differing only by their names are the same route
+        assert_eq!(router.get_route("/a/:y/b"), Some(&"y"));
+        assert_eq!(router.get_route("/a/:z/b"), Some(&"y"));
+        router.register("/a/:name", "name").unwrap();
+        let found = router.find("/a/1").unwrap();
+        assert_eq!(found.handler, &"name");
+        assert_eq!(found.get("name"), Some("1"));
+        assert_eq!(
+            router.iter().map(|(_, m)| *m).collect::<Vec<_>>(),
+            vec!["y", "star x", "star y", "name"]
+        );
+    }
+
+    #[test]
+    fn test_match_many_stars() {
+        let mut router = PathRouter::new();
+        router.register("/*a/x/*b/x/*c/x/*d/end", "stars").unwrap();
+
+        let path = "/x".repeat(120);
+        let mut params = BTreeMap::<String, String>::new();
+        assert_eq!(router.match_path(&path, &mut params), None);
+        assert!(router.match_all(&path).is_empty());
+
+        let path = alloc::format!("{path}/end");
+        assert_eq!(router.match_path(&path, &mut params), Some(&"stars"));
+        assert_eq!(params.get("a").map(|m| m.as_str()), Some("x"));
+    }
+
+    #[test]
+    fn test_match_prefix() {
+        let mut router = PathRouter::new();
+        router
+            .register("/admin", "admin")
+            .unwrap()
+            .register("/admin/:section/settings", "settings")
+            .unwrap()
+            .register("/blog/:slug", "blog")
+            .unwrap();
+
+        let found = router.match_prefix("/admin/users/1").unwrap();
+        assert_eq!(found.handler, &"admin");
+        assert_eq!(found.remainder, "/users/1");
+
+        let found = router.match_prefix("/admin/users/settings/theme").unwrap();
+        assert_eq!(found.handler, &"settings");
+        assert_eq!(found.get("section"), Some("users"));
+        assert_eq!(found.remainder, "/theme");
+
+        let found = router.match_prefix("/admin").unwrap();
+        assert_eq!(found.remainder, "");
+        assert_eq!(router.match_prefix("/admin/").unwrap().remainder, "");
+
+        assert_eq!(
+            router
+                .match_prefix("/blog/hello/comments")
+                .unwrap()
+                .get("slug"),
+            Some("hello")
+        );
+        assert!(router.match_prefix("/blog").is_none());
+
+        router.register("/", "root").unwrap();
+        assert_eq!(router.match_prefix("/blog").unwrap().remainder, "/blog");
+    }
 }
@@ -0,0 +1,185 @@
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+use crate::options::{EncodedSlash, MatchOptions};
+
+fn hex(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+fn escape_at(bytes: &[u8], idx: usize) -> Option<u8> {
+    if bytes.get(idx) != Some(&b'%') {
+        return None;
+    }
+    let high = hex(*bytes.get(idx + 1)?)?;
+    let low = hex(*bytes.get(idx + 2)?)?;
+    Some(high << 4 | low)
+}
+
+pub(crate) fn has_encoded_slash(input: &str) -> bool {
+    let bytes = input.as_bytes();
+    (0..bytes.len()).any(|idx| escape_at(bytes, idx) == Some(b'/'))
+}
+
+/// Decode `%XX` escapes. Invalid escapes are kept as is, and if the decoded
+/// bytes are not valid utf8 the input is returned untouched.
+pub(crate) fn decode(input: &str, keep_slash: bool) -> Cow<'_, str> {
+    if !input.contains('%') {
+        return Cow::Borrowed(input);
+    }
+
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match escape_at(bytes, idx) {
+            Some(b'/') if keep_slash => {
+                output.extend_from_slice(&bytes[idx..idx + 3]);
+                idx += 3;
+            }
+            Some(byte) => {
+                output.push(byte);
+                idx += 3;
+            }
+            None => {
+                output.push(bytes[idx]);
+                idx += 1;
+            }
+        }
+    }
+
+    match String::from_utf8(output) {
+        Ok(output) => Cow::Owned(output),
+        Err(_) => Cow::Borrowed(input),
+    }
+}
+
+/// Decode a captured value according to the options.
+/// Returns `None` when the value must be rejected
+pub(crate) fn decode_param<'a>(input: &'a str, options: &MatchOptions) -> Option<Cow<'a, str>> {
+    if options.encoded_slash == EncodedSlash::Reject && has_encoded_slash(input) {
+        return None;
+    }
+
+    if !options.decode_params {
+        return Some(Cow::Borrowed(input));
+    }
+
+    Some(decode(input, options.encoded_slash != EncodedSlash::Decode))
+}
+
+fn is_unescaped(ch: char) -> bool {
+    ch.is_alphanumeric()
+        || matches!(
+            ch,
+            '-' | '.' | '_' | '~' | '!' | '$' | '&' | '\'' | '+' | ',' | ';' | '=' | '@'
+        )
+        || (!ch.is_ascii() && !ch.is_whitespace() && !ch.is_control())
+}
+
+/// Whether a char can appear literally in a constant in a route template
+pub(crate) fn is_constant_char(ch: char) -> bool {
+    is_unescaped(ch) || ch == '%' || ch == '\\'
+}
+
+/// Chars with a meaning in templates which are written as `\:` etc. in a constant
+pub(crate) fn is_escapable(ch: char) -> bool {
+    matches!(ch, ':' | '*' | '(' | ')' | '\\')
+}
+
+/// Remove `\` escapes from a constant
+pub(crate) fn unescape(input: &str) -> Cow<'_, str> {
+    if !input.contains('\\') {
+        return Cow::Borrowed(input);
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => output.extend(chars.next()),
+            ch => output.push(ch),
+        }
+    }
+    Cow::Owned(output)
+}
+
+/// Escape everything in a constant which can't appear literally in a route template
+pub(crate) fn encode_constant(input: &str) -> Cow<'_, str> {
+    if input.chars().all(is_unescaped) {
+        return Cow::Borrowed(input);
+    }
+
+    let mut output = String::with_capacity(input.len());
+    for ch in input.chars() {
+        if is_unescaped(ch) {
+            output.push(ch);
+        } else if is_escapable(ch) {
+            output.push('\\');
+            output.push(ch);
+        } else {
+            push_encoded(&mut output, ch);
+        }
+    }
+    Cow::Owned(output)
+}
+
+/// Escape a constant for templates where `:`, `*`, `(` and `)` are literal and braces delimit parameters.
+/// Braces are doubled when `double_braces` is set, and percent-encoded otherwise
+pub(crate) fn encode_brace_constant(input: &str, double_braces: bool) -> Cow<'_, str> {
+    let is_literal = |ch: char| is_unescaped(ch) || matches!(ch, ':' | '*' | '(' | ')');
+
+    if input.chars().all(is_literal) {
+        return Cow::Borrowed(input);
+    }
+
+    let mut output = String::with_capacity(input.len());
+    for ch in input.chars() {
+        match ch {
+            ch if is_literal(ch) => output.push(ch),
+            '{' | '}' if double_braces => {
+                output.push(ch);
+                output.push(ch);
+            }
+            ch => push_encoded(&mut output, ch),
+        }
+    }
+    Cow::Owned(output)
+}
+
+fn push_encoded(output: &mut String, ch: char) {
+    let mut buf = [0; 4];
+    for byte in ch.encode_utf8(&mut buf).bytes() {
+        output.push_str(&alloc::format!("%{:02X}", byte));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("hello", false), "hello");
+        assert_eq!(decode("hello%20world", false), "hello world");
+        assert_eq!(decode("caf%C3%A9", false), "café");
+        assert_eq!(decode("a%2Fb", false), "a/b");
+        assert_eq!(decode("a%2fb", true), "a%2fb");
+        assert_eq!(decode("100%", false), "100%");
+        assert_eq!(decode("%zz", false), "%zz");
+        assert_eq!(decode("%FF", false), "%FF");
+    }
+
+    #[test]
+    fn test_encode_constant() {
+        assert_eq!(encode_constant("hello"), "hello");
+        assert_eq!(encode_constant("hello world"), "hello%20world");
+        assert_eq!(encode_constant("café"), "café");
+        assert_eq!(encode_constant("a/b%"), "a%2Fb%25");
+        assert_eq!(encode_constant("ns:core*"), "ns\\:core\\*");
+    }
+}
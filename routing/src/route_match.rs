@@ -0,0 +1,132 @@
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{Id, Params, Segments};
+
+/// A route found for a path, see [`PathRouter::find`](crate::PathRouter::find)
+#[derive(Debug)]
+pub struct Match<'r, 'p, H> {
+    pub handler: &'r H,
+    /// The template the route was registered with
+    pub template: &'r Segments<'static>,
+    /// Captured parameters in the order they appear in the path
+    pub params: Vec<(&'r str, Cow<'p, str>)>,
+    /// The node the path ended at
+    pub node: Id,
+    /// The part of the path which wasn't consumed by the template
+    pub remainder: &'p str,
+}
+
+impl<'r, 'p, H> Match<'r, 'p, H> {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.as_ref())
+    }
+
+    /// Copy the parameters into a [`Params`]
+    pub fn params_into<P: Params>(&self, params: &mut P) {
+        for (name, value) in &self.params {
+            params.set(Cow::Borrowed(*name), Cow::Borrowed(value.as_ref()));
+        }
+    }
+
+    pub fn map<F, U>(self, func: F) -> Match<'r, 'p, U>
+    where
+        F: FnOnce(&'r H) -> &'r U,
+    {
+        Match {
+            handler: func(self.handler),
+            template: self.template,
+            params: self.params,
+            node: self.node,
+            remainder: self.remainder,
+        }
+    }
+
+    pub fn to_owned(&self) -> OwnedMatch<H>
+    where
+        H: Clone,
+    {
+        OwnedMatch {
+            handler: self.handler.clone(),
+            template: self.template.clone(),
+            params: self
+                .params
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            node: self.node,
+            remainder: self.remainder.to_string(),
+        }
+    }
+}
+
+/// A [`Match`] which doesn't borrow from the router or the path
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct OwnedMatch<H> {
+    pub handler: H,
+    pub template: Segments<'static>,
+    pub params: Vec<(String, String)>,
+    pub node: Id,
+    pub remainder: String,
+}
+
+impl<H> OwnedMatch<H> {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl<'r, 'p, H: Clone> From<Match<'r, 'p, H>> for OwnedMatch<H> {
+    fn from(value: Match<'r, 'p, H>) -> Self {
+        value.to_owned()
+    }
+}
+
+/// Where to send a path which only differs from its template by a trailing slash,
+/// see [`TrailingSlash::RedirectToCanonical`](crate::TrailingSlash::RedirectToCanonical)
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub location: String,
+}
+
+impl Redirect {
+    /// The path with its trailing slash added or removed
+    pub(crate) fn toggle_slash(path: &str) -> Redirect {
+        let location = match path.strip_suffix('/') {
+            Some(location) => location.to_string(),
+            None => format!("{}/", path),
+        };
+        Redirect { location }
+    }
+}
+
+/// What a path resolved to, see [`PathRouter::resolve`](crate::PathRouter::resolve)
+#[derive(Debug)]
+pub enum Resolved<'r, 'p, H> {
+    Found(Match<'r, 'p, H>),
+    Redirect(Redirect),
+}
+
+impl<'r, 'p, H> Resolved<'r, 'p, H> {
+    pub fn map<F, U>(self, func: F) -> Resolved<'r, 'p, U>
+    where
+        F: FnOnce(&'r H) -> &'r U,
+    {
+        match self {
+            Resolved::Found(found) => Resolved::Found(found.map(func)),
+            Resolved::Redirect(redirect) => Resolved::Redirect(redirect),
+        }
+    }
+}
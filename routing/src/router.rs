@@ -1,11 +1,16 @@
-use alloc::{boxed::Box, format};
-use core::{fmt, str::FromStr};
+use alloc::{boxed::Box, format, sync::Arc};
+use core::{cell::Cell, convert::TryFrom, fmt, str::FromStr};
 
-use alloc::{vec, vec::Vec};
+use alloc::{string::String, vec, vec::Vec};
 
-use http::Method;
+use http::{request::Parts, Method};
 
-use crate::{AsSegments, Params, PathRouter, Segments};
+use crate::{
+    guard::Guard,
+    version::{VersionFallback, VersionResolver, Versions},
+    AsSegments, CaseSensitivity, Match, MatchOptions, Params, PathRouter, Redirect, Resolved,
+    Segments,
+};
 
 #[derive(Debug)]
 pub struct RouteError {
@@ -33,9 +38,11 @@ impl core::error::Error for RouteError {
 }
 
 bitflags::bitflags! {
+    /// A set of request methods. The bits above the standard methods are
+    /// handed out to extension methods by a [`MethodRegistry`]
     #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-    pub struct MethodFilter: u8 {
+    pub struct MethodFilter: u32 {
        const GET = 1 << 0;
        const POST = 1 << 1;
        const PUT = 1 << 2;
@@ -43,55 +50,98 @@ bitflags::bitflags! {
        const DELETE = 1 << 4;
        const HEAD = 1 << 5;
        const OPTIONS = 1 << 6;
+       const CONNECT = 1 << 7;
+       const TRACE = 1 << 8;
+
+       const _ = !0;
     }
 }
 
+const EXTENSION_SHIFT: u32 = 16;
+const EXTENSION_SLOTS: usize = 16;
+
 impl MethodFilter {
     pub fn any() -> MethodFilter {
         MethodFilter::all()
     }
+
+    fn extension(slot: usize) -> MethodFilter {
+        MethodFilter::from_bits_retain(1 << (EXTENSION_SHIFT as usize + slot))
+    }
+
+    // Moves extension methods to the flags another registry gave them
+    fn remap(self, slots: &[(MethodFilter, MethodFilter)]) -> MethodFilter {
+        let mut remapped = self;
+        for (from, _) in slots {
+            remapped.remove(*from);
+        }
+        for (from, to) in slots {
+            if self.contains(*from) {
+                remapped.insert(*to);
+            }
+        }
+        remapped
+    }
 }
 
+// Writes the methods as a list like `GET, POST`, extension methods are looked up in `extensions`
+fn fmt_methods(
+    f: &mut fmt::Formatter<'_>,
+    methods: MethodFilter,
+    extensions: &[String],
+) -> fmt::Result {
+    let names = methods.iter_names().map(|(name, _)| name).chain(
+        extensions
+            .iter()
+            .enumerate()
+            .filter(|(slot, _)| methods.contains(MethodFilter::extension(*slot)))
+            .map(|(_, name)| name.as_str()),
+    );
+
+    for (idx, name) in names.enumerate() {
+        if idx > 0 {
+            f.write_str(", ")?;
+        }
+        f.write_str(name)?;
+    }
+
+    Ok(())
+}
+
+// Parses a list like `GET, POST` or `GET|POST`
+fn parse_methods(
+    s: &str,
+    mut extension: impl FnMut(&str) -> Option<MethodFilter>,
+) -> Result<MethodFilter, RouteError> {
+    s.split([',', '|'])
+        .map(str::trim)
+        .try_fold(
+            MethodFilter::empty(),
+            |acc, name| match MethodFilter::from_name(name).or_else(|| extension(name)) {
+                Some(method) => Ok(acc | method),
+                None => Err(RouteError::new(format!("Unknown method: '{name}'"))),
+            },
+        )
+}
+
+/// Lists the standard methods, extension methods are named by [`MethodRegistry::display`]
 impl fmt::Display for MethodFilter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match *self {
-                MethodFilter::GET => "GET",
-                MethodFilter::POST => "POST",
-                MethodFilter::PUT => "PUT",
-                MethodFilter::PATCH => "PATCH",
-                MethodFilter::DELETE => "DELETE",
-                MethodFilter::HEAD => "HEAD",
-                MethodFilter::OPTIONS => "OPTIONS",
-                _ => "MULTIPLE",
-            }
-        )
+        fmt_methods(f, *self, &[])
     }
 }
 
 impl FromStr for MethodFilter {
     type Err = RouteError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let ret = match s {
-            "GET" => MethodFilter::GET,
-            "POST" => MethodFilter::POST,
-            "PATCH" => MethodFilter::PATCH,
-            "PUT" => MethodFilter::PUT,
-            "DELETE" => MethodFilter::DELETE,
-            "HEAD" => MethodFilter::HEAD,
-            "OPTIONS" => MethodFilter::OPTIONS,
-            _ => return Err(RouteError::new(format!("Unknown method: '{s}'"))),
-        };
-
-        Ok(ret)
+        parse_methods(s, |_| None)
     }
 }
 
-impl From<Method> for MethodFilter {
-    fn from(value: Method) -> Self {
-        match value {
+impl TryFrom<&Method> for MethodFilter {
+    type Error = RouteError;
+    fn try_from(value: &Method) -> Result<Self, Self::Error> {
+        let ret = match *value {
             Method::GET => MethodFilter::GET,
             Method::POST => MethodFilter::POST,
             Method::PATCH => MethodFilter::PATCH,
@@ -99,16 +149,178 @@ impl From<Method> for MethodFilter {
             Method::DELETE => MethodFilter::DELETE,
             Method::HEAD => MethodFilter::HEAD,
             Method::OPTIONS => MethodFilter::OPTIONS,
-            _ => todo!(),
-        }
+            Method::CONNECT => MethodFilter::CONNECT,
+            Method::TRACE => MethodFilter::TRACE,
+            _ => return Err(RouteError::new(format!("Unsupported method: '{value}'"))),
+        };
+
+        Ok(ret)
     }
 }
 
+impl TryFrom<Method> for MethodFilter {
+    type Error = RouteError;
+    fn try_from(value: Method) -> Result<Self, Self::Error> {
+        MethodFilter::try_from(&value)
+    }
+}
+
+/// Extension methods, like WebDAV's `PROPFIND`, by name. Each one is given
+/// a bit of its own in [`MethodFilter`], so the filters of one registry
+/// shouldn't be mixed with the ones of another
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MethodRegistry {
+    names: Vec<String>,
+}
+
+impl MethodRegistry {
+    pub fn new() -> MethodRegistry {
+        MethodRegistry::default()
+    }
+
+    /// The filter for a method name, registering it when it's new.
+    /// Standard methods give their own flag
+    pub fn register(&mut self, name: &str) -> Result<MethodFilter, RouteError> {
+        let method = Method::from_bytes(name.as_bytes()).map_err(RouteError::new)?;
+        if let Some(filter) = self.get(&method) {
+            return Ok(filter);
+        }
+
+        if self.names.len() == EXTENSION_SLOTS {
+            return Err(RouteError::new(format!(
+                "Too many extension methods, '{name}' doesn't fit"
+            )));
+        }
+
+        self.names.push(String::from(name));
+        Ok(MethodFilter::extension(self.names.len() - 1))
+    }
+
+    /// The filter for a request method, `None` for unregistered extension methods
+    pub fn get(&self, method: &Method) -> Option<MethodFilter> {
+        MethodFilter::try_from(method)
+            .ok()
+            .or_else(|| self.extension(method.as_str()))
+    }
+
+    /// Parse a list like `GET, PROPFIND`
+    pub fn parse(&self, s: &str) -> Result<MethodFilter, RouteError> {
+        parse_methods(s, |name| self.extension(name))
+    }
+
+    /// List the methods of a filter, including the extension methods
+    pub fn display(&self, methods: MethodFilter) -> DisplayMethods<'_> {
+        DisplayMethods {
+            methods,
+            extensions: &self.names,
+        }
+    }
+
+    fn extension(&self, name: &str) -> Option<MethodFilter> {
+        self.names
+            .iter()
+            .position(|m| m == name)
+            .map(MethodFilter::extension)
+    }
+
+    // Registers the extension methods of another registry, pairing the flag
+    // each one has there with its flag here
+    fn adopt(
+        &mut self,
+        other: &MethodRegistry,
+    ) -> Result<Vec<(MethodFilter, MethodFilter)>, RouteError> {
+        other
+            .names
+            .iter()
+            .enumerate()
+            .map(|(slot, name)| Ok((MethodFilter::extension(slot), self.register(name)?)))
+            .collect()
+    }
+}
+
+/// See [`MethodRegistry::display`]
 #[derive(Debug, Clone, Copy)]
+pub struct DisplayMethods<'a> {
+    methods: MethodFilter,
+    extensions: &'a [String],
+}
+
+impl fmt::Display for DisplayMethods<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_methods(f, self.methods, self.extensions)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone)]
 pub struct Entry<H> {
     pub method: MethodFilter,
     pub handler: H,
+    /// Guarded entries are only picked by [`Router::match_request`].
+    /// Guards can't be serialized, serializing a router with a guarded entry fails
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            skip_deserializing,
+            skip_serializing_if = "Option::is_none",
+            serialize_with = "refuse_guard"
+        )
+    )]
+    pub guard: Option<Arc<dyn Guard>>,
+    /// Versioned entries are only picked by [`Router::match_versioned`]
+    pub versions: Option<Versions>,
+}
+
+// Dropping the guard would turn the entry into one answering every request
+#[cfg(feature = "serde")]
+fn refuse_guard<S: serde::Serializer>(
+    _guard: &Option<Arc<dyn Guard>>,
+    _serializer: S,
+) -> Result<S::Ok, S::Error> {
+    Err(serde::ser::Error::custom(
+        "guarded entries can't be serialized",
+    ))
+}
+
+impl<H> Entry<H> {
+    /// Whether the entry handles the method without looking at the request
+    pub fn accepts(&self, method: MethodFilter) -> bool {
+        self.guard.is_none() && self.versions.is_none() && self.method.contains(method)
+    }
+
+    /// Whether the entry handles the method and its guard passes
+    pub fn accepts_request(&self, method: MethodFilter, parts: &Parts) -> bool {
+        self.versions.is_none() && self.method.contains(method) && self.passes(parts)
+    }
+
+    fn passes(&self, parts: &Parts) -> bool {
+        self.guard.as_ref().is_none_or(|m| m.check(parts))
+    }
+
+    // Whether both entries would answer the same requests
+    fn conflicts(&self, other: &Entry<H>) -> bool {
+        let versions = match (self.versions, other.versions) {
+            (None, None) => true,
+            (Some(a), Some(b)) => a.overlaps(&b),
+            _ => false,
+        };
+        self.guard.is_none()
+            && other.guard.is_none()
+            && self.method.contains(other.method)
+            && versions
+    }
+}
+
+impl<H: fmt::Debug> fmt::Debug for Entry<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Entry")
+            .field("method", &self.method)
+            .field("handler", &self.handler)
+            .field("guarded", &self.guard.is_some())
+            .field("versions", &self.versions)
+            .finish()
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -117,23 +329,139 @@ pub struct Route<H> {
     pub entries: Vec<Entry<H>>,
 }
 
+impl<H> Route<H> {
+    /// The union of the methods of all entries
+    pub fn allowed(&self) -> MethodFilter {
+        self.entries
+            .iter()
+            .fold(MethodFilter::empty(), |acc, m| acc | m.method)
+    }
+}
+
+/// Methods a [`Router`] answers for routes without an entry of their own
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct AutoMethods {
+    /// HEAD falls back to the GET entry, see [`RouteOutcome::Head`]
+    pub head: bool,
+    /// OPTIONS is answered with the allowed methods, see [`RouteOutcome::Options`]
+    pub options: bool,
+}
+
+/// What a path and method resolved to, see [`Router::lookup`]
+#[derive(Debug)]
+pub enum RouteOutcome<'r, 'p, H> {
+    Found(Match<'r, 'p, H>),
+    /// A HEAD request answered by the GET handler, the response body has to be dropped
+    Head(Match<'r, 'p, H>),
+    /// An OPTIONS request for a route without an OPTIONS handler
+    Options {
+        allow: MethodFilter,
+    },
+    /// A route matches the path, but has no handler for the method
+    MethodNotAllowed {
+        allowed: MethodFilter,
+    },
+    /// The path only differs from a route by its trailing slash, see
+    /// [`TrailingSlash::RedirectToCanonical`](crate::TrailingSlash::RedirectToCanonical)
+    Redirect(Redirect),
+    /// A route has handlers for the method, but not for the requested version
+    UnsupportedVersion {
+        requested: u32,
+    },
+    NotFound,
+}
+
+/// A [`RouteOutcome`] along with the version of the picked handler, see [`Router::match_versioned`]
+#[derive(Debug)]
+pub struct VersionedOutcome<'r, 'p, H> {
+    pub outcome: RouteOutcome<'r, 'p, H>,
+    /// `None` when the handler isn't versioned
+    pub version: Option<u32>,
+}
+
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub struct Router<H> {
     inner: PathRouter<Route<H>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    auto: AutoMethods,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    versioning: Option<Arc<dyn VersionResolver>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    fallback: VersionFallback,
+    #[cfg_attr(feature = "serde", serde(default))]
+    methods: MethodRegistry,
+}
+
+impl<H> Default for Router<H> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<H> Router<H> {
     pub fn new() -> Router<H> {
         Router {
             inner: PathRouter::new(),
+            auto: AutoMethods::default(),
+            versioning: None,
+            fallback: VersionFallback::default(),
+            methods: MethodRegistry::default(),
+        }
+    }
+
+    pub fn with_options(options: MatchOptions) -> Router<H> {
+        Router {
+            inner: PathRouter::with_options(options),
+            auto: AutoMethods::default(),
+            versioning: None,
+            fallback: VersionFallback::default(),
+            methods: MethodRegistry::default(),
         }
     }
 
+    /// Answer HEAD and OPTIONS for routes which don't handle them themselves
+    pub fn with_auto_methods(mut self, auto: AutoMethods) -> Router<H> {
+        self.auto = auto;
+        self
+    }
+
+    pub fn auto_methods(&self) -> AutoMethods {
+        self.auto
+    }
+
+    /// How [`match_versioned`](Router::match_versioned) finds the requested version,
+    /// and which one to use when there is none
+    pub fn with_versioning<V: VersionResolver + 'static>(
+        mut self,
+        resolver: V,
+        fallback: VersionFallback,
+    ) -> Router<H> {
+        self.versioning = Some(Arc::new(resolver));
+        self.fallback = fallback;
+        self
+    }
+
+    /// The filter for an extension method like `PROPFIND`, which
+    /// [`match_request`](Router::match_request) recognizes from then on
+    pub fn register_method(&mut self, name: &str) -> Result<MethodFilter, RouteError> {
+        self.methods.register(name)
+    }
+
+    pub fn methods(&self) -> &MethodRegistry {
+        &self.methods
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (&crate::Segments<'_>, &Route<H>)> {
         self.inner.iter()
     }
 
+    /// See [`PathRouter::iter_by_precedence`]
+    pub fn iter_by_precedence(&self) -> impl Iterator<Item = (&crate::Segments<'_>, &Route<H>)> {
+        self.inner.iter_by_precedence()
+    }
+
     pub fn map<T, U>(self, mapper: T) -> Router<U>
     where
         T: Fn(H, Option<&Segments<'_>>) -> U + Copy,
@@ -146,55 +474,185 @@ impl<H> Router<H> {
                     .map(move |m| Entry {
                         handler: mapper(m.handler, segments),
                         method: m.method,
+                        guard: m.guard,
+                        versions: m.versions,
                     })
                     .collect(),
             }),
+            auto: self.auto,
+            versioning: self.versioning,
+            fallback: self.fallback,
+            methods: self.methods,
         }
     }
 
-    pub fn mount<'a, S: AsSegments<'a>>(
-        &mut self,
-        path: S,
-        router: Router<H>,
-    ) -> Result<(), S::Error> {
-        self.inner.mount(path, router.inner)?;
-        Ok(())
+    pub fn mount<'a, S>(&mut self, path: S, router: Router<H>) -> Result<(), RouteError>
+    where
+        S: AsSegments<'a>,
+        S::Error: Into<Box<dyn core::error::Error + Send + Sync>>,
+    {
+        let inner = self.adopt(router)?;
+        self.inner.mount(path, inner).map_err(RouteError::new)
     }
 
     pub fn merge(&mut self, router: Router<H>) -> Result<(), RouteError> {
-        self.inner.merge(router.inner);
+        let inner = self.adopt(router)?;
+        self.inner.merge(inner);
         Ok(())
     }
 
+    // The routes of another router, with its extension methods registered here
+    fn adopt(&mut self, router: Router<H>) -> Result<PathRouter<Route<H>>, RouteError> {
+        let slots = self.methods.adopt(&router.methods)?;
+        if slots.iter().all(|(from, to)| from == to) {
+            return Ok(router.inner);
+        }
+
+        Ok(router.inner.map(|route, _| Route {
+            entries: route
+                .entries
+                .into_iter()
+                .map(|m| Entry {
+                    method: m.method.remap(&slots),
+                    ..m
+                })
+                .collect(),
+        }))
+    }
+
     pub fn route(
         &mut self,
         method: MethodFilter,
         path: &str,
         handler: H,
     ) -> Result<(), RouteError> {
-        if let Some(route) = self.inner.get_route_mut(path) {
-            if route
-                .entries
-                .iter()
-                .find(|m| m.method.contains(method))
-                .is_some()
-            {
+        let case = self.inner.options().case_sensitivity;
+        self.route_with_case(method, path, case, handler)
+    }
+
+    /// Add a route whose constants are compared with the given sensitivity
+    /// instead of the one in the router options
+    pub fn route_with_case(
+        &mut self,
+        method: MethodFilter,
+        path: &str,
+        case: CaseSensitivity,
+        handler: H,
+    ) -> Result<(), RouteError> {
+        self.add_entry(
+            path,
+            case,
+            Entry {
+                method,
+                handler,
+                guard: None,
+                versions: None,
+            },
+        )
+    }
+
+    /// Add a handler to a path which is preferred over paths of a lower priority, see
+    /// [`PathRouter::register_with_priority`]. The priority belongs to the path, so it
+    /// applies to every handler on it. A path given several priorities keeps the highest
+    pub fn route_with_priority(
+        &mut self,
+        method: MethodFilter,
+        path: &str,
+        priority: i32,
+        handler: H,
+    ) -> Result<(), RouteError> {
+        let case = self.inner.options().case_sensitivity;
+        let current = self.inner.priority_with_case(path, case);
+        self.route_with_case(method, path, case, handler)?;
+        let priority = current.map_or(priority, |m| m.max(priority));
+        self.inner.set_priority_with_case(path, case, priority);
+        Ok(())
+    }
+
+    /// Add a handler which is only picked by [`match_request`](Router::match_request)
+    /// when the guard passes. Guarded handlers are tried in the order they were added,
+    /// before the unguarded one for the method
+    pub fn route_with_guard<G: Guard + 'static>(
+        &mut self,
+        method: MethodFilter,
+        path: &str,
+        guard: G,
+        handler: H,
+    ) -> Result<(), RouteError> {
+        let case = self.inner.options().case_sensitivity;
+        self.add_entry(
+            path,
+            case,
+            Entry {
+                method,
+                handler,
+                guard: Some(Arc::new(guard)),
+                versions: None,
+            },
+        )
+    }
+
+    /// Add a handler for a range of API versions, which is only picked by
+    /// [`match_versioned`](Router::match_versioned)
+    pub fn route_with_version(
+        &mut self,
+        method: MethodFilter,
+        path: &str,
+        versions: Versions,
+        handler: H,
+    ) -> Result<(), RouteError> {
+        if versions.min > versions.max {
+            return Err(RouteError::new(format!(
+                "Empty version range {}..={}",
+                versions.min, versions.max
+            )));
+        }
+
+        let case = self.inner.options().case_sensitivity;
+        self.add_entry(
+            path,
+            case,
+            Entry {
+                method,
+                handler,
+                guard: None,
+                versions: Some(versions),
+            },
+        )
+    }
+
+    fn add_entry(
+        &mut self,
+        path: &str,
+        case: CaseSensitivity,
+        entry: Entry<H>,
+    ) -> Result<(), RouteError> {
+        if let Some(route) = self.inner.get_route_with_case_mut(path, case) {
+            if entry.guard.is_some() {
+                let idx = route
+                    .entries
+                    .iter()
+                    .position(|m| m.guard.is_none())
+                    .unwrap_or(route.entries.len());
+                route.entries.insert(idx, entry);
+                return Ok(());
+            }
+
+            if route.entries.iter().any(|m| m.conflicts(&entry)) {
                 return Err(RouteError {
                     inner: Box::from("Route already defined"),
                 });
             }
 
-            route.entries.push(Entry { method, handler });
+            route.entries.push(entry);
         } else {
             self.inner
-                .register(
+                .register_with_case(
                     path,
                     Route {
-                        entries: vec![Entry {
-                            method: method,
-                            handler,
-                        }],
+                        entries: vec![entry],
                     },
+                    case,
                 )
                 .map_err(|err| RouteError {
                     inner: Box::new(err),
@@ -212,7 +670,7 @@ impl<H> Router<H> {
     ) -> Option<(&H, MethodFilter)> {
         self.inner.match_path(path, params).and_then(|m| {
             m.entries.iter().find_map(|m| {
-                if m.method.contains(method) {
+                if m.accepts(method) {
                     Some((&m.handler, m.method))
                 } else {
                     None
@@ -221,7 +679,216 @@ impl<H> Router<H> {
         })
     }
 
-    pub fn match_routes<'a, P: Params>(
+    /// Find the handler for a path and method along with the template and captures
+    pub fn find<'p>(&self, path: &'p str, method: MethodFilter) -> Option<Match<'_, 'p, H>> {
+        let found = self.inner.find(path)?;
+        let entry = found.handler.entries.iter().find(|m| m.accepts(method))?;
+        Some(found.map(|_| &entry.handler))
+    }
+
+    /// Like [`find`](Router::find), redirecting paths which differ from their template by
+    /// a trailing slash when the router was built with
+    /// [`TrailingSlash::RedirectToCanonical`](crate::TrailingSlash::RedirectToCanonical).
+    /// Redirects are given without looking at the method
+    pub fn resolve<'p>(&self, path: &'p str, method: MethodFilter) -> Option<Resolved<'_, 'p, H>> {
+        match self.inner.resolve(path)? {
+            Resolved::Found(found) => {
+                let entry = found.handler.entries.iter().find(|m| m.accepts(method))?;
+                Some(Resolved::Found(found.map(|_| &entry.handler)))
+            }
+            Resolved::Redirect(redirect) => Some(Resolved::Redirect(redirect)),
+        }
+    }
+
+    /// Find the handler for a path and method, telling a path without a route
+    /// apart from a route without a handler for the method. Guarded and versioned
+    /// handlers are left out, a method only they handle is [`RouteOutcome::NotFound`]
+    pub fn lookup<'p>(&self, path: &'p str, method: MethodFilter) -> RouteOutcome<'_, 'p, H> {
+        let usable = |m: &Entry<H>| m.guard.is_none() && m.versions.is_none();
+        self.outcome(path, Some(method), usable, |route, method| {
+            route.entries.iter().find(|m| m.accepts(method))
+        })
+    }
+
+    /// Like [`lookup`](Router::lookup) for a request, picking the first handler whose
+    /// method and guard both pass. A method whose handlers all refuse the request is
+    /// [`RouteOutcome::NotFound`]. Extension methods are known once they're registered
+    /// with [`register_method`](Router::register_method)
+    pub fn match_request<'p>(&self, parts: &'p Parts) -> RouteOutcome<'_, 'p, H> {
+        let method = self.methods.get(&parts.method);
+        let usable = |m: &Entry<H>| m.versions.is_none() && m.passes(parts);
+        self.outcome(parts.uri.path(), method, usable, |route, method| {
+            route
+                .entries
+                .iter()
+                .find(|m| m.accepts_request(method, parts))
+        })
+    }
+
+    /// Like [`match_request`](Router::match_request), preferring the handler for the
+    /// version the request asks for, see [`with_versioning`](Router::with_versioning).
+    /// Unversioned handlers are used when no versioned one fits
+    pub fn match_versioned<'p>(&self, parts: &'p Parts) -> VersionedOutcome<'_, 'p, H> {
+        let (requested, path) = match &self.versioning {
+            Some(resolver) => (resolver.version(parts), resolver.path(parts)),
+            None => (None, parts.uri.path()),
+        };
+        let method = self.methods.get(&parts.method);
+
+        let target = requested.or(match self.fallback {
+            VersionFallback::Latest => None,
+            VersionFallback::Default(version) => Some(version),
+        });
+        let usable = |m: &Entry<H>| {
+            m.passes(parts)
+                && m.versions
+                    .is_none_or(|v| target.is_none_or(|version| v.contains(version)))
+        };
+
+        let chosen = Cell::new(None);
+        let missed = Cell::new(None);
+        let outcome = self.outcome(path, method, usable, |route, method| {
+            let candidates = || {
+                route
+                    .entries
+                    .iter()
+                    .filter(move |m| m.method.contains(method) && m.passes(parts))
+            };
+
+            let version = requested.or_else(|| match self.fallback {
+                VersionFallback::Latest => candidates()
+                    .filter_map(|m| m.versions)
+                    .map(|m| m.latest())
+                    .max(),
+                VersionFallback::Default(version) => Some(version),
+            });
+
+            if let Some(version) = version {
+                let entry = candidates().find(|m| m.versions.is_some_and(|v| v.contains(version)));
+                if entry.is_some() {
+                    chosen.set(Some(version));
+                    return entry;
+                }
+            }
+
+            let entry = candidates().find(|m| m.versions.is_none());
+            if entry.is_none() && candidates().next().is_some() {
+                missed.set(version);
+            }
+            entry
+        });
+
+        match (outcome, missed.get()) {
+            (RouteOutcome::NotFound, Some(requested)) => VersionedOutcome {
+                outcome: RouteOutcome::UnsupportedVersion { requested },
+                version: None,
+            },
+            (outcome, _) => VersionedOutcome {
+                outcome,
+                version: chosen.get(),
+            },
+        }
+    }
+
+    // Only the methods of `usable` entries are reported as allowed. A method which has
+    // handlers, none of them picked, is not found rather than not allowed
+    fn outcome<'r, 'p>(
+        &'r self,
+        path: &'p str,
+        method: Option<MethodFilter>,
+        usable: impl Fn(&Entry<H>) -> bool,
+        pick: impl Fn(&'r Route<H>, MethodFilter) -> Option<&'r Entry<H>>,
+    ) -> RouteOutcome<'r, 'p, H> {
+        let found = match self.inner.resolve(path) {
+            Some(Resolved::Found(found)) => found,
+            Some(Resolved::Redirect(redirect)) => return RouteOutcome::Redirect(redirect),
+            None => return RouteOutcome::NotFound,
+        };
+
+        let route = found.handler;
+        let allowed = self.allowed(route, &usable);
+        let reachable = route.entries.iter().any(&usable);
+
+        let Some(method) = method else {
+            return if reachable {
+                RouteOutcome::MethodNotAllowed { allowed }
+            } else {
+                RouteOutcome::NotFound
+            };
+        };
+
+        if let Some(entry) = pick(route, method) {
+            return RouteOutcome::Found(found.map(|_| &entry.handler));
+        }
+
+        let head = self.auto.head && method == MethodFilter::HEAD;
+        if head {
+            if let Some(entry) = pick(route, MethodFilter::GET) {
+                return RouteOutcome::Head(found.map(|_| &entry.handler));
+            }
+        }
+
+        let handled = |method| route.entries.iter().any(|m| m.method.contains(method));
+        if !reachable || handled(method) || (head && handled(MethodFilter::GET)) {
+            RouteOutcome::NotFound
+        } else if self.auto.options && method == MethodFilter::OPTIONS {
+            RouteOutcome::Options { allow: allowed }
+        } else {
+            RouteOutcome::MethodNotAllowed { allowed }
+        }
+    }
+
+    // The methods of the route's usable entries plus the ones answered automatically
+    fn allowed(&self, route: &Route<H>, usable: impl Fn(&Entry<H>) -> bool) -> MethodFilter {
+        let mut allowed = route
+            .entries
+            .iter()
+            .filter(|m| usable(m))
+            .fold(MethodFilter::empty(), |allowed, m| allowed | m.method);
+        if self.auto.head && allowed.contains(MethodFilter::GET) {
+            allowed |= MethodFilter::HEAD;
+        }
+        if self.auto.options {
+            allowed |= MethodFilter::OPTIONS;
+        }
+        allowed
+    }
+
+    /// The methods the route for the path has unguarded and unversioned handlers for,
+    /// empty when there is no route
+    pub fn allowed_methods(&self, path: &str) -> MethodFilter {
+        self.inner
+            .find(path)
+            .map(|found| self.allowed(found.handler, |m| m.guard.is_none() && m.versions.is_none()))
+            .unwrap_or_else(MethodFilter::empty)
+    }
+
+    /// The deepest prefix route with a handler for the method, see [`PathRouter::match_prefix`]
+    pub fn match_prefix<'p>(
+        &self,
+        path: &'p str,
+        method: MethodFilter,
+    ) -> Option<Match<'_, 'p, H>> {
+        self.inner.find_prefix(path, |found| {
+            let entry = found.handler.entries.iter().find(|m| m.accepts(method))?;
+            Some(found.map(|_| &entry.handler))
+        })
+    }
+
+    /// Every route matching the path with a handler for the method, from most to least specific.
+    /// See [`PathRouter::match_all`]
+    pub fn match_all<'p>(&self, path: &'p str, method: MethodFilter) -> Vec<Match<'_, 'p, H>> {
+        self.inner
+            .match_all(path)
+            .into_iter()
+            .filter_map(|found| {
+                let entry = found.handler.entries.iter().find(|m| m.accepts(method))?;
+                Some(found.map(|_| &entry.handler))
+            })
+            .collect()
+    }
+
+    pub fn match_routes<P: Params>(
         &self,
         path: &str,
         method: MethodFilter,
@@ -245,15 +912,530 @@ pub struct RouteMatchIter<'a, H> {
 impl<'a, H> Iterator for RouteMatchIter<'a, H> {
     type Item = (&'a H, MethodFilter);
     fn next(&mut self) -> Option<Self::Item> {
-        let Some(iter) = self.inner.as_mut() else {
-            return None;
-        };
+        let iter = self.inner.as_mut()?;
 
         loop {
             let next = iter.next()?;
-            if next.method.contains(self.method) {
+            if next.accepts(self.method) {
                 return Some((&next.handler, next.method));
             }
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_lookup() {
+        let mut router = Router::new();
+        router
+            .route(MethodFilter::GET | MethodFilter::HEAD, "/users/:id", "show")
+            .unwrap();
+        router
+            .route(MethodFilter::DELETE, "/users/:id", "delete")
+            .unwrap();
+
+        match router.lookup("/users/1", MethodFilter::DELETE) {
+            RouteOutcome::Found(found) => {
+                assert_eq!(found.handler, &"delete");
+                assert_eq!(found.get("id"), Some("1"));
+            }
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+
+        let allowed = MethodFilter::GET | MethodFilter::HEAD | MethodFilter::DELETE;
+        assert!(matches!(
+            router.lookup("/users/1", MethodFilter::POST),
+            RouteOutcome::MethodNotAllowed { allowed: m } if m == allowed
+        ));
+        assert!(matches!(
+            router.lookup("/posts/1", MethodFilter::GET),
+            RouteOutcome::NotFound
+        ));
+
+        assert_eq!(router.allowed_methods("/users/1"), allowed);
+        assert!(router.allowed_methods("/posts").is_empty());
+    }
+
+    #[test]
+    fn test_trailing_slash() {
+        let mut router = Router::new();
+        router.route(MethodFilter::GET, "/path/", "get").unwrap();
+        router.route(MethodFilter::POST, "/path", "post").unwrap();
+        assert!(router.route(MethodFilter::GET, "/path", "dup").is_err());
+
+        for path in ["/path", "/path/"] {
+            match router.lookup(path, MethodFilter::GET) {
+                RouteOutcome::Found(found) => assert_eq!(found.handler, &"get"),
+                outcome => panic!("unexpected {:?}", outcome),
+            }
+            match router.lookup(path, MethodFilter::POST) {
+                RouteOutcome::Found(found) => assert_eq!(found.handler, &"post"),
+                outcome => panic!("unexpected {:?}", outcome),
+            }
+            assert_eq!(
+                router.allowed_methods(path),
+                MethodFilter::GET | MethodFilter::POST
+            );
+        }
+
+        let mut strict = Router::with_options(MatchOptions {
+            trailing_slash: crate::TrailingSlash::Strict,
+            ..Default::default()
+        });
+        strict.route(MethodFilter::GET, "/path/", "get").unwrap();
+        strict.route(MethodFilter::POST, "/path", "post").unwrap();
+        assert!(matches!(
+            strict.lookup("/path", MethodFilter::GET),
+            RouteOutcome::MethodNotAllowed { allowed } if allowed == MethodFilter::POST
+        ));
+    }
+
+    #[test]
+    fn test_auto_methods() {
+        let mut router = Router::new();
+        router
+            .route(MethodFilter::GET, "/users/:id", "show")
+            .unwrap();
+        router
+            .route(MethodFilter::POST, "/users", "create")
+            .unwrap();
+
+        assert!(matches!(
+            router.lookup("/users/1", MethodFilter::HEAD),
+            RouteOutcome::MethodNotAllowed { .. }
+        ));
+
+        let router = router.with_auto_methods(AutoMethods {
+            head: true,
+            options: true,
+        });
+
+        match router.lookup("/users/1", MethodFilter::HEAD) {
+            RouteOutcome::Head(found) => {
+                assert_eq!(found.handler, &"show");
+                assert_eq!(found.get("id"), Some("1"));
+            }
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+
+        let allow = MethodFilter::GET | MethodFilter::HEAD | MethodFilter::OPTIONS;
+        assert!(matches!(
+            router.lookup("/users/1", MethodFilter::OPTIONS),
+            RouteOutcome::Options { allow: m } if m == allow
+        ));
+        assert!(matches!(
+            router.lookup("/users", MethodFilter::HEAD),
+            RouteOutcome::MethodNotAllowed { allowed: m }
+                if m == MethodFilter::POST | MethodFilter::OPTIONS
+        ));
+        assert_eq!(router.allowed_methods("/users/1"), allow);
+    }
+
+    #[test]
+    fn test_method_filter() {
+        assert_eq!(
+            MethodFilter::try_from(&Method::TRACE).unwrap(),
+            MethodFilter::TRACE
+        );
+        let propfind = Method::from_bytes(b"PROPFIND").unwrap();
+        assert!(MethodFilter::try_from(&propfind).is_err());
+
+        let methods = MethodFilter::GET | MethodFilter::POST;
+        assert_eq!(methods.to_string(), "GET, POST");
+        assert_eq!("GET, POST".parse::<MethodFilter>().unwrap(), methods);
+        assert_eq!("GET|POST".parse::<MethodFilter>().unwrap(), methods);
+        assert!("GET, PROPFIND".parse::<MethodFilter>().is_err());
+        assert!("".parse::<MethodFilter>().is_err());
+
+        let mut registry = MethodRegistry::new();
+        let filter = registry.register("PROPFIND").unwrap();
+        let mkcol = registry.register("MKCOL").unwrap();
+        assert_eq!(registry.register("PROPFIND").unwrap(), filter);
+        assert_eq!(registry.register("GET").unwrap(), MethodFilter::GET);
+        assert!(registry.register("BAD METHOD").is_err());
+        assert_ne!(filter, mkcol);
+        assert_eq!(registry.get(&propfind), Some(filter));
+        assert_eq!(
+            registry.parse("GET, PROPFIND").unwrap(),
+            MethodFilter::GET | filter
+        );
+        assert_eq!(
+            registry.display(MethodFilter::GET | filter).to_string(),
+            "GET, PROPFIND"
+        );
+
+        let mut router = Router::new();
+        router.route(filter, "/files/*path", "propfind").unwrap();
+        assert!(router.find("/files/a", filter).is_some());
+        assert!(router.find("/files/a", mkcol).is_none());
+        assert!(router.find("/files/a", MethodFilter::GET).is_none());
+    }
+
+    #[test]
+    fn test_param_names() {
+        let mut router = Router::new();
+        router
+            .route(MethodFilter::GET, "/users/:id", "get")
+            .unwrap();
+        router
+            .route(MethodFilter::DELETE, "/users/:user_id", "delete")
+            .unwrap();
+
+        // Both handlers share the route registered first, along with its names
+        match router.lookup("/users/1", MethodFilter::DELETE) {
+            RouteOutcome::Found(found) => {
+                assert_eq!(found.handler, &"delete");
+                assert_eq!(found.get("id"), Some("1"));
+            }
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+        assert_eq!(
+            router.find("/users/1", MethodFilter::GET).unwrap().handler,
+            &"get"
+        );
+        assert_eq!(router.iter().count(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_guards() {
+        let mut router = Router::new();
+        router
+            .route(MethodFilter::POST, "/upload", "upload")
+            .unwrap();
+        let json = serde_json::to_string(&router).unwrap();
+        let router: Router<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            router.find("/upload", MethodFilter::POST).unwrap().handler,
+            "upload"
+        );
+
+        let mut router = Router::new();
+        router
+            .route_with_guard(MethodFilter::POST, "/upload", |_: &Parts| false, "typed")
+            .unwrap();
+        assert!(serde_json::to_string(&router).is_err());
+    }
+
+    #[test]
+    fn test_mount() {
+        let mut router = Router::new();
+        let propfind = router.register_method("PROPFIND").unwrap();
+        router.route(propfind, "/", "root").unwrap();
+
+        let mut api = Router::new();
+        let mkcol = api.register_method("MKCOL").unwrap();
+        api.route(mkcol, "/dav", "dav").unwrap();
+        router.mount("/api", api).unwrap();
+        let mkcol = router.methods().parse("MKCOL").unwrap();
+        assert_eq!(router.find("/api/dav", mkcol).unwrap().handler, &"dav");
+
+        let mut crowded = Router::new();
+        for idx in 0..EXTENSION_SLOTS {
+            crowded.register_method(&format!("EXT{idx}")).unwrap();
+        }
+        crowded.route(MethodFilter::GET, "/", "crowded").unwrap();
+        assert!(router.mount("/crowded", crowded).is_err());
+    }
+
+    #[test]
+    fn test_route_with_priority() {
+        let mut router = Router::new();
+        router
+            .route(MethodFilter::GET, "/files/index", "index")
+            .unwrap();
+        router
+            .route_with_priority(MethodFilter::GET, "/files/*path", 1, "files")
+            .unwrap();
+        router
+            .route(MethodFilter::POST, "/files/*path", "upload")
+            .unwrap();
+
+        let found = router.find("/files/index", MethodFilter::GET).unwrap();
+        assert_eq!(found.handler, &"files");
+        let found = router.find("/files/index", MethodFilter::POST).unwrap();
+        assert_eq!(found.handler, &"upload");
+
+        let order = router
+            .iter_by_precedence()
+            .map(|(segments, _)| segments.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!["/files/*path", "/files/index"]);
+
+        // A lower priority given later doesn't take the path's priority away
+        router
+            .route_with_priority(MethodFilter::PUT, "/files/*path", 0, "replace")
+            .unwrap();
+        let found = router.find("/files/index", MethodFilter::GET).unwrap();
+        assert_eq!(found.handler, &"files");
+        router
+            .route_with_priority(MethodFilter::GET, "/archive/:id", -1, "archive")
+            .unwrap();
+        router
+            .route(MethodFilter::GET, "/:section/latest", "latest")
+            .unwrap();
+        let found = router.find("/archive/latest", MethodFilter::GET).unwrap();
+        assert_eq!(found.handler, &"latest");
+    }
+
+    #[test]
+    fn test_match_request() {
+        let mut router = Router::new();
+        router
+            .route(MethodFilter::POST, "/upload", "fallback")
+            .unwrap();
+        router
+            .route_with_guard(
+                MethodFilter::POST,
+                "/upload",
+                |parts: &Parts| parts.headers.get("content-type").is_some(),
+                "typed",
+            )
+            .unwrap();
+        assert!(router
+            .route(MethodFilter::POST, "/upload", "duplicate")
+            .is_err());
+
+        let request = |content_type: Option<&str>| {
+            let mut request = http::Request::post("/upload");
+            if let Some(content_type) = content_type {
+                request = request.header("content-type", content_type);
+            }
+            request.body(()).unwrap().into_parts().0
+        };
+
+        let with = request(Some("text/plain"));
+        match router.match_request(&with) {
+            RouteOutcome::Found(found) => assert_eq!(found.handler, &"typed"),
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+        let without = request(None);
+        match router.match_request(&without) {
+            RouteOutcome::Found(found) => assert_eq!(found.handler, &"fallback"),
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+
+        assert_eq!(
+            router.find("/upload", MethodFilter::POST).unwrap().handler,
+            &"fallback"
+        );
+
+        // Handlers refusing the request don't make their method allowed
+        router
+            .route_with_guard(
+                MethodFilter::POST,
+                "/typed",
+                |parts: &Parts| parts.headers.get("content-type").is_some(),
+                "typed",
+            )
+            .unwrap();
+        router.route(MethodFilter::GET, "/typed", "form").unwrap();
+        let parts = |method: &str| {
+            http::Request::builder()
+                .method(method)
+                .uri("/typed")
+                .body(())
+                .unwrap()
+                .into_parts()
+                .0
+        };
+        assert!(matches!(
+            router.match_request(&parts("POST")),
+            RouteOutcome::NotFound
+        ));
+        assert!(matches!(
+            router.match_request(&parts("DELETE")),
+            RouteOutcome::MethodNotAllowed { allowed } if allowed == MethodFilter::GET
+        ));
+        assert!(matches!(
+            router.lookup("/typed", MethodFilter::POST),
+            RouteOutcome::NotFound
+        ));
+        assert_eq!(router.allowed_methods("/typed"), MethodFilter::GET);
+
+        let propfind = http::Request::builder()
+            .method("PROPFIND")
+            .uri("/upload")
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0;
+        assert!(matches!(
+            router.match_request(&propfind),
+            RouteOutcome::MethodNotAllowed { .. }
+        ));
+
+        let method = router.register_method("PROPFIND").unwrap();
+        router.route(method, "/upload", "props").unwrap();
+        match router.match_request(&propfind) {
+            RouteOutcome::Found(found) => assert_eq!(found.handler, &"props"),
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+
+        // Extension methods of a merged router keep their names
+        let mut other = Router::new();
+        let mkcol = other.register_method("MKCOL").unwrap();
+        let lock = other.register_method("LOCK").unwrap();
+        other.route(mkcol | lock, "/dav", "dav").unwrap();
+        assert_eq!(other.methods().display(mkcol).to_string(), "MKCOL");
+        router.merge(other).unwrap();
+        let lock = http::Request::builder()
+            .method("LOCK")
+            .uri("/dav")
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0;
+        match router.match_request(&lock) {
+            RouteOutcome::Found(found) => assert_eq!(found.handler, &"dav"),
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+        match router.match_request(&propfind) {
+            RouteOutcome::Found(found) => assert_eq!(found.handler, &"props"),
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+        assert_eq!(
+            router
+                .methods()
+                .display(router.allowed_methods("/dav"))
+                .to_string(),
+            "MKCOL, LOCK"
+        );
+    }
+
+    #[test]
+    fn test_match_versioned() {
+        use crate::version::HeaderVersion;
+
+        let mut router = Router::new();
+        router
+            .route_with_version(MethodFilter::GET, "/users/:id", Versions::only(1), "v1")
+            .unwrap();
+        router
+            .route_with_version(MethodFilter::GET, "/users/:id", Versions::since(2), "v2")
+            .unwrap();
+        assert!(router
+            .route_with_version(
+                MethodFilter::GET,
+                "/users/:id",
+                Versions::between(2, 3),
+                "dup"
+            )
+            .is_err());
+        router
+            .route_with_version(
+                MethodFilter::GET,
+                "/teams",
+                Versions::between(2, 3),
+                "teams",
+            )
+            .unwrap();
+        router
+            .route(MethodFilter::GET, "/health", "health")
+            .unwrap();
+
+        let router = router.with_versioning(
+            HeaderVersion(http::HeaderName::from_static("api-version")),
+            VersionFallback::Latest,
+        );
+        let request = |path: &str, version: Option<&str>| {
+            let mut request = http::Request::get(path);
+            if let Some(version) = version {
+                request = request.header("api-version", version);
+            }
+            request.body(()).unwrap().into_parts().0
+        };
+
+        let parts = request("/users/1", Some("1"));
+        let found = router.match_versioned(&parts);
+        assert_eq!(found.version, Some(1));
+        assert!(matches!(found.outcome, RouteOutcome::Found(m) if m.handler == &"v1"));
+
+        let parts = request("/users/1", Some("5"));
+        let found = router.match_versioned(&parts);
+        assert_eq!(found.version, Some(5));
+        assert!(matches!(found.outcome, RouteOutcome::Found(m) if m.handler == &"v2"));
+
+        let parts = request("/users/1", None);
+        let found = router.match_versioned(&parts);
+        assert_eq!(found.version, Some(2));
+        assert!(matches!(found.outcome, RouteOutcome::Found(m) if m.handler == &"v2"));
+
+        let parts = request("/teams", Some("1"));
+        assert!(matches!(
+            router.match_versioned(&parts).outcome,
+            RouteOutcome::UnsupportedVersion { requested: 1 }
+        ));
+        let parts = request("/teams", None);
+        assert_eq!(router.match_versioned(&parts).version, Some(3));
+
+        let parts = request("/health", Some("2"));
+        let found = router.match_versioned(&parts);
+        assert_eq!(found.version, None);
+        assert!(matches!(found.outcome, RouteOutcome::Found(m) if m.handler == &"health"));
+
+        // Versioned handlers are only there for match_versioned
+        assert!(router.find("/users/1", MethodFilter::GET).is_none());
+        let parts = request("/users/1", Some("1"));
+        assert!(matches!(
+            router.match_request(&parts),
+            RouteOutcome::NotFound
+        ));
+        assert!(matches!(
+            router.lookup("/users/1", MethodFilter::GET),
+            RouteOutcome::NotFound
+        ));
+
+        let mut router = router;
+        router
+            .route_with_version(MethodFilter::POST, "/health", Versions::only(1), "ping")
+            .unwrap();
+        assert!(matches!(
+            router.lookup("/health", MethodFilter::POST),
+            RouteOutcome::NotFound
+        ));
+        assert!(matches!(
+            router.lookup("/health", MethodFilter::DELETE),
+            RouteOutcome::MethodNotAllowed { allowed } if allowed == MethodFilter::GET
+        ));
+        assert!(router
+            .route_with_version(
+                MethodFilter::GET,
+                "/empty",
+                Versions { min: 5, max: 2 },
+                "empty"
+            )
+            .is_err());
+
+        let router = router.with_versioning(
+            HeaderVersion(http::HeaderName::from_static("api-version")),
+            VersionFallback::Default(1),
+        );
+        let parts = request("/users/1", None);
+        let found = router.match_versioned(&parts);
+        assert_eq!(found.version, Some(1));
+        assert!(matches!(found.outcome, RouteOutcome::Found(m) if m.handler == &"v1"));
+
+        // A version which can't be read counts as none
+        let parts = request("/users/1", Some("latest"));
+        let found = router.match_versioned(&parts);
+        assert_eq!(found.version, Some(1));
+        assert!(matches!(found.outcome, RouteOutcome::Found(m) if m.handler == &"v1"));
+
+        assert!(matches!(
+            router.match_versioned(&request("/teams", None)).outcome,
+            RouteOutcome::UnsupportedVersion { requested: 1 }
+        ));
+        assert!(matches!(
+            router
+                .match_versioned(&request("/teams", Some("9")))
+                .outcome,
+            RouteOutcome::UnsupportedVersion { requested: 9 }
+        ));
+    }
+}
@@ -1,19 +1,39 @@
-use alloc::{borrow::Cow, fmt, string::ToString};
+use alloc::{borrow::Cow, boxed::Box, fmt, string::ToString, vec::Vec};
+
+use crate::{percent::encode_constant, Constraint};
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Segment<'a> {
+    /// Percent-decoded text, escapes are added back when displayed
     Constant(Cow<'a, str>),
-    Parameter(Cow<'a, str>),
+    Parameter(Cow<'a, str>, Option<Constraint<'a>>),
     Star(Cow<'a, str>),
+    /// Constants and parameters sharing one path component, eg. `:name.:ext` or `v:version`
+    Composite(Vec<Segment<'a>>),
+    /// `:lang?` or `*rest?`, the segment may be left out of the path
+    Optional(Box<Segment<'a>>),
+    /// `:kind(users|people)` or `(users|people)`, one of several constants.
+    /// The component is captured when the alternation is named
+    Alternation(Option<Cow<'a, str>>, Vec<Cow<'a, str>>),
 }
 
 impl<'a> Segment<'a> {
     pub fn to_owned(self) -> Segment<'static> {
         match self {
             Segment::Constant(constant) => Segment::Constant(constant.to_string().into()),
-            Segment::Parameter(param) => Segment::Parameter(param.to_string().into()),
+            Segment::Parameter(param, constraint) => {
+                Segment::Parameter(param.to_string().into(), constraint.map(|m| m.to_owned()))
+            }
             Segment::Star(star) => Segment::Star(star.to_string().into()),
+            Segment::Composite(parts) => {
+                Segment::Composite(parts.into_iter().map(|m| m.to_owned()).collect())
+            }
+            Segment::Optional(segment) => Segment::Optional(Box::new(segment.to_owned())),
+            Segment::Alternation(name, options) => Segment::Alternation(
+                name.map(|m| m.to_string().into()),
+                options.into_iter().map(|m| m.to_string().into()).collect(),
+            ),
         }
     }
 
@@ -22,26 +42,77 @@ impl<'a> Segment<'a> {
     }
 
     pub fn parameter(s: impl Into<Cow<'a, str>>) -> Segment<'a> {
-        Segment::Parameter(s.into())
+        Segment::Parameter(s.into(), None)
+    }
+
+    pub fn constrained(s: impl Into<Cow<'a, str>>, constraint: Constraint<'a>) -> Segment<'a> {
+        Segment::Parameter(s.into(), Some(constraint))
     }
 
     pub fn star(s: impl Into<Cow<'a, str>>) -> Segment<'a> {
         Segment::Star(s.into())
     }
 
+    pub fn composite(parts: Vec<Segment<'a>>) -> Segment<'a> {
+        Segment::Composite(parts)
+    }
+
+    pub fn optional(segment: Segment<'a>) -> Segment<'a> {
+        Segment::Optional(Box::new(segment))
+    }
+
+    pub fn alternation<I, S>(options: I) -> Segment<'a>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Cow<'a, str>>,
+    {
+        Segment::Alternation(None, options.into_iter().map(Into::into).collect())
+    }
+
+    pub fn named_alternation<I, S>(name: impl Into<Cow<'a, str>>, options: I) -> Segment<'a>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Cow<'a, str>>,
+    {
+        Segment::Alternation(
+            Some(name.into()),
+            options.into_iter().map(Into::into).collect(),
+        )
+    }
+
+    pub fn is_optional(&self) -> bool {
+        matches!(self, Segment::Optional(_))
+    }
+
+    /// The constant text or the name of the parameter.
+    /// For composites this is the first part, for alternations the name or else the first option
     pub fn as_str(&self) -> &str {
         match self {
             Segment::Constant(c) => c,
-            Segment::Parameter(p) => p,
+            Segment::Parameter(p, _) => p,
             Segment::Star(s) => s,
+            Segment::Composite(parts) => parts.first().map(|m| m.as_str()).unwrap_or_default(),
+            Segment::Optional(s) => s.as_str(),
+            Segment::Alternation(Some(name), _) => name,
+            Segment::Alternation(None, options) => {
+                options.first().map(|m| m.as_ref()).unwrap_or_default()
+            }
         }
     }
 
     pub fn into_inner(self) -> Cow<'a, str> {
         match self {
             Segment::Constant(c) => c,
-            Segment::Parameter(p) => p,
+            Segment::Parameter(p, _) => p,
             Segment::Star(s) => s,
+            Segment::Composite(parts) => parts
+                .into_iter()
+                .next()
+                .map(|m| m.into_inner())
+                .unwrap_or_default(),
+            Segment::Optional(s) => s.into_inner(),
+            Segment::Alternation(Some(name), _) => name,
+            Segment::Alternation(None, options) => options.into_iter().next().unwrap_or_default(),
         }
     }
 }
@@ -49,9 +120,30 @@ impl<'a> Segment<'a> {
 impl<'a> fmt::Display for Segment<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Segment::Constant(c) => f.write_str(c),
-            Segment::Parameter(p) => write!(f, ":{}", p),
+            Segment::Constant(c) => f.write_str(&encode_constant(c)),
+            Segment::Parameter(p, None) => write!(f, ":{}", p),
+            Segment::Parameter(p, Some(c)) => write!(f, ":{}<{}>", p, c),
             Segment::Star(s) => write!(f, "*{}", s),
+            Segment::Composite(parts) => {
+                for part in parts {
+                    write!(f, "{}", part)?;
+                }
+                Ok(())
+            }
+            Segment::Optional(s) => write!(f, "{}?", s),
+            Segment::Alternation(name, options) => {
+                if let Some(name) = name {
+                    write!(f, ":{}", name)?;
+                }
+                f.write_str("(")?;
+                for (idx, option) in options.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str("|")?;
+                    }
+                    f.write_str(&encode_constant(option))?;
+                }
+                f.write_str(")")
+            }
         }
     }
 }
@@ -1,9 +1,15 @@
-use crate::parser::parse;
 use crate::segment::Segment;
+use crate::{
+    dialect::{DisplayAs, UnsupportedSegment},
+    error::RouteSyntaxError,
+    parser::parse,
+    Dialect,
+};
 use alloc::{
     fmt,
     slice::Iter,
     string::String,
+    vec,
     vec::{IntoIter, Vec},
 };
 
@@ -27,6 +33,64 @@ impl<'a> Segments<'a> {
     pub fn len(&self) -> usize {
         self.0.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether the template ends in a slash, kept as a last empty constant
+    pub fn has_trailing_slash(&self) -> bool {
+        matches!(self.0.last(), Some(Segment::Constant(c)) if c.is_empty())
+    }
+
+    /// How many parameters the template captures, stars and named alternations included
+    pub fn param_count(&self) -> usize {
+        fn count(segment: &Segment<'_>) -> usize {
+            match segment {
+                Segment::Constant(_) | Segment::Alternation(None, _) => 0,
+                Segment::Parameter(..) | Segment::Star(_) | Segment::Alternation(Some(_), _) => 1,
+                Segment::Composite(parts) => parts.iter().map(count).sum(),
+                Segment::Optional(segment) => count(segment),
+            }
+        }
+
+        self.0.iter().map(count).sum()
+    }
+
+    /// Render the template in another dialect, fails if a segment has no equivalent there
+    pub fn display_as(&self, dialect: Dialect) -> Result<DisplayAs<'_, 'a>, UnsupportedSegment> {
+        if let Some(segment) = self.0.iter().find(|m| !dialect.supports(m)) {
+            return Err(UnsupportedSegment::new(dialect, segment));
+        }
+
+        Ok(DisplayAs {
+            segments: self,
+            dialect,
+        })
+    }
+
+    /// Every concrete path the template describes, with optional segments
+    /// either included or left out. The variant with everything included comes first.
+    pub fn variants(&self) -> Vec<Vec<&Segment<'a>>> {
+        let mut variants = vec![Vec::with_capacity(self.0.len())];
+        for segment in &self.0 {
+            match segment {
+                Segment::Optional(inner) => {
+                    let without = variants.clone();
+                    for variant in &mut variants {
+                        variant.push(&**inner);
+                    }
+                    variants.extend(without);
+                }
+                segment => {
+                    for variant in &mut variants {
+                        variant.push(segment);
+                    }
+                }
+            }
+        }
+        variants
+    }
 }
 
 impl<'a> AsRef<[Segment<'a>]> for Segments<'a> {
@@ -75,6 +139,7 @@ impl<'a> AsSegments<'a> for Segments<'a> {
 pub trait AsSegments<'a> {
     type Error;
     type Iter: Iterator<Item = Segment<'a>>;
+    #[allow(clippy::wrong_self_convention)]
     fn as_segments(self) -> Result<Self::Iter, Self::Error>;
 }
 
@@ -88,9 +153,9 @@ impl<'a> AsSegments<'a> for Vec<Segment<'a>> {
 
 impl<'a, 'c> AsSegments<'a> for &'c [Segment<'a>] {
     type Error = core::convert::Infallible;
-    type Iter = IntoIter<Segment<'a>>;
+    type Iter = core::iter::Cloned<Iter<'c, Segment<'a>>>;
     fn as_segments(self) -> Result<Self::Iter, Self::Error> {
-        Ok(self.to_vec().into_iter())
+        Ok(self.iter().cloned())
     }
 }
 
@@ -135,7 +200,7 @@ macro_rules! slice_impl {
 slice_impl!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22);
 
 impl<'a> AsSegments<'a> for &'a str {
-    type Error = udled::Error;
+    type Error = RouteSyntaxError;
     type Iter = IntoIter<Segment<'a>>;
     fn as_segments(self) -> Result<Self::Iter, Self::Error> {
         let segments = parse(self)?;
@@ -144,7 +209,7 @@ impl<'a> AsSegments<'a> for &'a str {
 }
 
 impl<'a> AsSegments<'a> for &'a String {
-    type Error = udled::Error;
+    type Error = RouteSyntaxError;
     type Iter = IntoIter<Segment<'a>>;
     fn as_segments(self) -> Result<Self::Iter, Self::Error> {
         let segments = parse(self)?;
@@ -153,7 +218,7 @@ impl<'a> AsSegments<'a> for &'a String {
 }
 
 impl<'a> AsSegments<'a> for String {
-    type Error = udled::Error;
+    type Error = RouteSyntaxError;
     type Iter = IntoIter<Segment<'a>>;
     fn as_segments(self) -> Result<Self::Iter, Self::Error> {
         let segments = parse(&self)?;
@@ -0,0 +1,249 @@
+use core::fmt;
+
+use alloc::string::String;
+use http::{header::ACCEPT, request::Parts, HeaderName};
+
+/// The API versions an [`Entry`](crate::router::Entry) handles, both ends included
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Versions {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl Versions {
+    pub fn only(version: u32) -> Versions {
+        Versions::between(version, version)
+    }
+
+    /// The version and every later one
+    pub fn since(version: u32) -> Versions {
+        Versions::between(version, u32::MAX)
+    }
+
+    /// Panics when `min` is above `max`, the range would be empty
+    pub fn between(min: u32, max: u32) -> Versions {
+        assert!(min <= max, "empty version range {}..={}", min, max);
+        Versions { min, max }
+    }
+
+    pub fn contains(&self, version: u32) -> bool {
+        self.min <= version && version <= self.max
+    }
+
+    pub fn overlaps(&self, other: &Versions) -> bool {
+        self.min <= other.max && other.min <= self.max
+    }
+
+    /// The highest version named by the range
+    pub(crate) fn latest(&self) -> u32 {
+        if self.max == u32::MAX {
+            self.min
+        } else {
+            self.max
+        }
+    }
+}
+
+/// Finds the API version a request asks for
+pub trait VersionResolver: fmt::Debug + Send + Sync {
+    fn version(&self, parts: &Parts) -> Option<u32>;
+
+    /// The path to match, for resolvers which take the version from it
+    fn path<'p>(&self, parts: &'p Parts) -> &'p str {
+        parts.uri.path()
+    }
+}
+
+/// The first resolver finding a version wins
+impl<A: VersionResolver, B: VersionResolver> VersionResolver for (A, B) {
+    fn version(&self, parts: &Parts) -> Option<u32> {
+        self.0.version(parts).or_else(|| self.1.version(parts))
+    }
+
+    fn path<'p>(&self, parts: &'p Parts) -> &'p str {
+        if self.0.version(parts).is_some() {
+            self.0.path(parts)
+        } else {
+            self.1.path(parts)
+        }
+    }
+}
+
+/// A header like `Api-Version: 2`, a `v` in front and a minor version are allowed
+#[derive(Debug, Clone)]
+pub struct HeaderVersion(pub HeaderName);
+
+impl VersionResolver for HeaderVersion {
+    fn version(&self, parts: &Parts) -> Option<u32> {
+        let value = parts.headers.get(&self.0)?.to_str().ok()?;
+        parse_version(value.trim())
+    }
+}
+
+/// A vendor media type in `Accept`, like `application/vnd.acme.v2+json` for the vendor `acme`
+#[derive(Debug, Clone)]
+pub struct MediaTypeVersion {
+    vendor: String,
+}
+
+impl MediaTypeVersion {
+    pub fn new(vendor: &str) -> MediaTypeVersion {
+        MediaTypeVersion {
+            vendor: vendor.to_ascii_lowercase(),
+        }
+    }
+
+    fn parse(&self, media_type: &str) -> Option<u32> {
+        let mut params = media_type.split(';');
+        let essence = params.next()?.trim().to_ascii_lowercase();
+
+        // `q=0` refuses the media type
+        let refused = params.filter_map(|m| m.split_once('=')).any(|(name, q)| {
+            name.trim().eq_ignore_ascii_case("q") && q.trim().parse::<f32>() == Ok(0.0)
+        });
+        if refused {
+            return None;
+        }
+
+        let (_, subtype) = essence.split_once('/')?;
+        let version = subtype
+            .strip_prefix("vnd.")?
+            .strip_prefix(self.vendor.as_str())?
+            .strip_prefix('.')?;
+        let version = version.split('+').next()?;
+        parse_version(version)
+    }
+}
+
+impl VersionResolver for MediaTypeVersion {
+    fn version(&self, parts: &Parts) -> Option<u32> {
+        parts
+            .headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .find_map(|media_type| self.parse(media_type))
+    }
+}
+
+/// A first path component like `/v2`, which is left out when matching
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PathVersion;
+
+impl PathVersion {
+    fn split(path: &str) -> Option<(u32, &str)> {
+        let rest = path.strip_prefix('/')?;
+        let end = rest.find('/').unwrap_or(rest.len());
+        let version = rest[..end].strip_prefix('v')?;
+        if version.is_empty() || !version.bytes().all(|m| m.is_ascii_digit()) {
+            return None;
+        }
+
+        let path = if end == rest.len() { "/" } else { &rest[end..] };
+        Some((version.parse().ok()?, path))
+    }
+}
+
+impl VersionResolver for PathVersion {
+    fn version(&self, parts: &Parts) -> Option<u32> {
+        PathVersion::split(parts.uri.path()).map(|(version, _)| version)
+    }
+
+    fn path<'p>(&self, parts: &'p Parts) -> &'p str {
+        match PathVersion::split(parts.uri.path()) {
+            Some((_, path)) => path,
+            None => parts.uri.path(),
+        }
+    }
+}
+
+// `2`, `v2` or `2.1`, minor versions are ignored
+fn parse_version(input: &str) -> Option<u32> {
+    let input = input
+        .strip_prefix('v')
+        .or_else(|| input.strip_prefix('V'))
+        .unwrap_or(input);
+    let major = input.split('.').next()?;
+    if major.is_empty() || !major.bytes().all(|m| m.is_ascii_digit()) {
+        return None;
+    }
+    major.parse().ok()
+}
+
+/// The version used when a request doesn't ask for one
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum VersionFallback {
+    /// The highest version the route names for the method
+    #[default]
+    Latest,
+    Default(u32),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use http::Request;
+
+    fn parts(request: http::request::Builder) -> Parts {
+        request.body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn test_resolvers() {
+        let header = HeaderVersion(HeaderName::from_static("api-version"));
+        assert_eq!(
+            header.version(&parts(Request::get("/").header("api-version", "v2"))),
+            Some(2)
+        );
+        assert_eq!(
+            header.version(&parts(Request::get("/").header("api-version", "3.1"))),
+            Some(3)
+        );
+        assert_eq!(
+            header.version(&parts(Request::get("/").header("api-version", "x"))),
+            None
+        );
+
+        let media = MediaTypeVersion::new("acme");
+        let request = parts(
+            Request::get("/").header("accept", "text/html, application/vnd.acme.v2+json;q=0.9"),
+        );
+        assert_eq!(media.version(&request), Some(2));
+        assert_eq!(
+            media.version(&parts(
+                Request::get("/").header("accept", "application/vnd.other.v2+json")
+            )),
+            None
+        );
+        let request = parts(Request::get("/").header(
+            "accept",
+            "application/vnd.acme.v3+json;q=0, application/vnd.acme.v1+json",
+        ));
+        assert_eq!(media.version(&request), Some(1));
+        let request = parts(Request::get("/").header("accept", "application/vnd.acme.v3; Q=0.0"));
+        assert_eq!(media.version(&request), None);
+
+        let request = parts(Request::get("/v4/users/1"));
+        assert_eq!(PathVersion.version(&request), Some(4));
+        assert_eq!(PathVersion.path(&request), "/users/1");
+        let request = parts(Request::get("/v4"));
+        assert_eq!(PathVersion.path(&request), "/");
+        let request = parts(Request::get("/values"));
+        assert_eq!(PathVersion.version(&request), None);
+        assert_eq!(PathVersion.path(&request), "/values");
+
+        let both = (media, PathVersion);
+        assert_eq!(both.version(&parts(Request::get("/v1/a"))), Some(1));
+        assert_eq!(both.path(&parts(Request::get("/v1/a"))), "/a");
+    }
+
+    #[test]
+    #[should_panic(expected = "empty version range")]
+    fn test_empty_versions() {
+        Versions::between(5, 2);
+    }
+}